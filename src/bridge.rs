@@ -0,0 +1,158 @@
+// ************************************************************************************************
+// DESCRIPTION
+// ************************************************************************************************
+
+//! # bridge.rs
+//!
+//! Helpers wiring soft timers and scheduler tasks together.
+//!
+//! * arm_task_timer() starts a timer and lets a task wait for it, so the
+//!   task resumes in the first process cycle after the timer expired.
+//!
+
+// ************************************************************************************************
+// USES
+// ************************************************************************************************
+
+use crate::scheduler::{Error, Scheduler};
+use crate::softtimer::{SofTimers, SoftTimerErr, TimerCounter};
+use crate::task::TaskId;
+
+// ************************************************************************************************
+// TYPES AND STRUCTURES
+// ************************************************************************************************
+
+/// Error of a helper using both subsystems.
+#[derive(Debug, PartialEq)]
+pub enum BridgeError {
+    Timer(SoftTimerErr),
+    Scheduler(Error),
+}
+
+// ************************************************************************************************
+// IMPLEMENTATIONS
+// ************************************************************************************************
+
+/// Starts the one shot timer `handle` with `threshold` ticks and puts the
+/// task `task_id` into waiting state until the timer expires. The task is
+/// checked first, so the timer is not started for an unknown task. The
+/// pool may use any counter type, see SofTimers::with_counter().
+///
+/// # Examples
+///
+/// ```
+/// use lwos::bridge::arm_task_timer;
+/// use lwos::scheduler::Scheduler;
+/// use lwos::softtimer::SofTimers;
+/// use lwos::task::{Task, TaskState, TaskId, Execute, TaskControl};
+///
+/// struct SomeExecuter {}
+/// impl Execute for SomeExecuter {
+///     fn execute(&mut self, _id : TaskId) -> TaskControl {
+///         TaskControl::Continue
+///     }
+/// }
+///
+/// let timers = SofTimers::new();
+/// let handle = timers.create().unwrap();
+/// let mut executer = SomeExecuter {};
+/// let mut scheduler: Scheduler::<3> = Scheduler::new();
+/// let id = scheduler.add(Task::new(TaskState::Running, &mut executer)).unwrap();
+///
+/// assert_eq!(arm_task_timer(&timers, handle, 10, &mut scheduler, id), Ok(()));
+/// assert_eq!(scheduler.get(id).unwrap().state, TaskState::Waiting);
+/// ```
+pub fn arm_task_timer<'a, const SIZE: usize, S: Default, C: TimerCounter>(
+    timers: &'a SofTimers<C>,
+    handle: usize,
+    threshold: C,
+    scheduler: &mut Scheduler<'a, SIZE, S>,
+    task_id: TaskId,
+) -> Result<(), BridgeError> {
+    let task = scheduler.get(task_id).map_err(BridgeError::Scheduler)?;
+
+    timers
+        .start(handle, threshold, false)
+        .map_err(BridgeError::Timer)?;
+    task.wait_timer(timers, handle);
+
+    Ok(())
+}
+
+// ************************************************************************************************
+// TESTS
+// ************************************************************************************************
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::{Execute, Task, TaskControl, TaskState};
+    use core::cell::Cell;
+
+    struct CountExecuter<'c> {
+        runs: &'c Cell<usize>,
+    }
+    impl Execute for CountExecuter<'_> {
+        fn execute(&mut self, _id: TaskId) -> TaskControl {
+            self.runs.set(self.runs.get() + 1);
+            TaskControl::Continue
+        }
+    }
+
+    #[test]
+    fn bridge_arm_task_timer() {
+        let runs = Cell::new(0usize);
+        let timers = SofTimers::new();
+        let handle = timers.create().unwrap();
+        let mut executer = CountExecuter { runs: &runs };
+        let mut scheduler: Scheduler<2> = Scheduler::new();
+        let id = scheduler
+            .add(Task::new(TaskState::Running, &mut executer))
+            .unwrap();
+
+        assert_eq!(
+            arm_task_timer(&timers, handle, 3, &mut scheduler, 1),
+            Err(BridgeError::Scheduler(Error::NoSuchTaskId))
+        );
+        assert_eq!(timers.running_count(), 0);
+        assert_eq!(
+            arm_task_timer(&timers, handle, 3, &mut scheduler, id),
+            Ok(())
+        );
+
+        for _ in 0..3 {
+            scheduler.process();
+            assert_eq!(runs.get(), 0);
+            timers.update();
+        }
+
+        scheduler.process();
+        assert_eq!(runs.get(), 1);
+        assert_eq!(scheduler.get(id).unwrap().state, TaskState::Running);
+    }
+
+    #[test]
+    fn bridge_arm_task_timer_u16() {
+        let runs = Cell::new(0usize);
+        let timers: SofTimers<u16> = SofTimers::with_counter();
+        let handle = timers.create().unwrap();
+        let mut executer = CountExecuter { runs: &runs };
+        let mut scheduler: Scheduler<1> = Scheduler::new();
+        let id = scheduler
+            .add(Task::new(TaskState::Running, &mut executer))
+            .unwrap();
+
+        assert_eq!(
+            arm_task_timer(&timers, handle, 2u16, &mut scheduler, id),
+            Ok(())
+        );
+        for _ in 0..2 {
+            scheduler.process();
+            timers.update();
+        }
+        assert_eq!(runs.get(), 0);
+
+        scheduler.process();
+        assert_eq!(runs.get(), 1);
+    }
+}
@@ -1,5 +1,14 @@
 #![no_std]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
+pub mod bridge;
 pub mod scheduler;
+#[cfg(feature = "std")]
+pub mod sim;
 pub mod softtimer;
 pub mod task;
 
@@ -11,6 +20,22 @@ pub enum SignalState {
     NotSignaled,
     Signaled,
 }
-trait Signal {
+pub trait Signal {
     fn get_signal_state(&self) -> SignalState;
 }
+
+/// Error reported by a signal source which cannot determine its state,
+/// e.g. a faulty sensor.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SignalError;
+
+/// Signal source which may fail. Use `Signal` for infallible sources.
+pub trait TrySignal {
+    fn try_get_signal_state(&self) -> Result<SignalState, SignalError>;
+}
+
+/// Monotonic time source, e.g. a hardware tick counter. The unit of the
+/// time values is up to the application.
+pub trait Clock {
+    fn now(&self) -> u64;
+}
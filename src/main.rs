@@ -5,8 +5,9 @@ struct PrintExecuter {
     msg: &'static str,
 }
 impl lwos::Execute for PrintExecuter {
-    fn execute(&mut self, _id: lwos::TaskId) {
+    fn execute(&mut self, _id: lwos::TaskId) -> lwos::TaskControl {
         println!("{}", self.msg);
+        lwos::TaskControl::Continue
     }
 }
 
@@ -18,22 +19,30 @@ struct CountExecuter {
 }
 
 impl lwos::Execute for CountExecuter {
-    fn execute(&mut self, _id: lwos::TaskId) {
+    fn execute(&mut self, _id: lwos::TaskId) -> lwos::TaskControl {
         println!("CountExecuter {}", self.count);
-        self.count = self.count + 1;
+        self.count += 1;
+        lwos::TaskControl::Continue
     }
 }
 
 fn main() {
-    let mut hello_executer = PrintExecuter { msg: "Hello" };
-    let mut scheduler_executer = PrintExecuter { msg: "scheduler" };
-    let mut world_executer = PrintExecuter { msg: "world!\r\n" };
-    let mut counter_executer = CountExecuter { count: 0usize };
-
-    let hello_task = lwos::Task::new(lwos::TaskState::Running, &mut hello_executer);
-    let scheduler_task = lwos::Task::new(lwos::TaskState::Running, &mut scheduler_executer);
-    let world_task = lwos::Task::new(lwos::TaskState::Running, &mut world_executer);
-    let counter_task = lwos::Task::new(lwos::TaskState::Running, &mut counter_executer);
+    let hello_task = lwos::Task::new_boxed(
+        lwos::TaskState::Running,
+        Box::new(PrintExecuter { msg: "Hello" }),
+    );
+    let scheduler_task = lwos::Task::new_boxed(
+        lwos::TaskState::Running,
+        Box::new(PrintExecuter { msg: "scheduler" }),
+    );
+    let world_task = lwos::Task::new_boxed(
+        lwos::TaskState::Running,
+        Box::new(PrintExecuter { msg: "world!\r\n" }),
+    );
+    let counter_task = lwos::Task::new_boxed(
+        lwos::TaskState::Running,
+        Box::new(CountExecuter { count: 0usize }),
+    );
 
     let mut task_ids: [lwos::TaskId; TASKS] = [lwos::INVALID_ID; TASKS];
     let mut scheduler: lwos::Scheduler<TASKS> = lwos::Scheduler::new();
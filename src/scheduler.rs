@@ -2,23 +2,203 @@
 //! tasks will be executed in the order they are registered.
 
 use super::task::*;
+use crate::Clock;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+/// Guard against concurrent access to the scheduler, e.g. from interrupt
+/// handlers suspending or resuming tasks.
+pub trait CriticalSection {
+    /// Runs `f` inside the critical section.
+    fn with<R>(&self, f: impl FnOnce() -> R) -> R;
+}
 
 /// Definition for the Scheduler data structure which can
 /// manage a set of task stored internally as an array.
-pub struct Scheduler<'a, const SIZE: usize> {
-    tasks: [Option<Task<'a>>; SIZE],
+/// Each task of the array gets a scratch value of type `S`, which its
+/// executer (implementing `Execute<S>`) accesses by Context::scratch().
+pub struct Scheduler<'a, const SIZE: usize, S = ()> {
+    tasks: [Option<Task<'a, S>>; SIZE],
+    deadlock_threshold: Option<usize>,
+    fairness_state: Option<u64>,
+    random_state: u64,
+    successors: [TaskId; SIZE],
+    aging_period: Option<usize>,
+    cycles: u64,
+    budget_cursor: usize,
+    step_mode: bool,
+    min_rates: [u64; SIZE],
+    last_runs: [u64; SIZE],
+    runs: [u64; SIZE],
+    run_gates: [Option<RunGate>; SIZE],
+    inboxes: [Inbox; SIZE],
+    subscriptions: [u32; SIZE],
+    ceilings: [Option<u8>; SIZE],
+    reserved: [bool; SIZE],
+    added: [u64; SIZE],
+    generations: [u32; SIZE],
+    insertions: u64,
+    yield_clock: Option<&'a dyn Clock>,
+    yield_budget: u64,
+    cpu_clock: Option<&'a dyn Clock>,
+    cpu_times: [u64; SIZE],
+    cpu_total: u64,
+    yield_target: Option<TaskId>,
+    max_yield_depth: usize,
+    peak_yield_depth: usize,
+    tag: usize,
+    scratch: [S; SIZE],
+    frozen: Option<[TaskId; SIZE]>,
+    enabled_mask: u32,
+    deferred: Deferred,
+    deferred_actions: [Option<&'a mut dyn FnMut()>; SIZE],
+    last_cycle_start: Option<u64>,
+    jitter: Option<(u64, u64, u64)>,
+    eviction_policy: Option<EvictionPolicy<'a, SIZE, S>>,
+    order_hash: u64,
+    cycle_orders: [Option<u64>; 2],
+    #[cfg(feature = "metrics")]
+    metrics: Metrics,
+    #[cfg(feature = "alloc")]
+    overflow: Vec<Option<Task<'a, S>>>,
+    #[cfg(feature = "alloc")]
+    overflow_limit: usize,
+}
+
+/// Policy choosing a task to evict when adding to a full scheduler, see
+/// Scheduler::set_eviction_policy().
+pub type EvictionPolicy<'a, const SIZE: usize, S> =
+    &'a mut dyn FnMut(&Scheduler<'a, SIZE, S>) -> Option<TaskId>;
+
+/// Instrumentation counters of a scheduler (requires the `metrics`
+/// feature).
+#[cfg(feature = "metrics")]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Metrics {
+    /// Number of process cycles run.
+    pub process_calls: usize,
+    /// Number of task slots scanned by all cycles.
+    pub slot_scans: usize,
+    /// Number of task executions by all cycles.
+    pub executions: usize,
+    /// Highest number of task executions in a single cycle.
+    pub max_executions: usize,
+}
+
+/// Task ID bound to the scheduler it was created by, see
+/// Scheduler::handle().
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Handle {
+    id: TaskId,
+    tag: usize,
+    generation: u32,
+}
+
+/// Executer running a whole scheduler as a single task of its parent, so
+/// schedulers compose into a tree. Each execution runs one process() cycle
+/// of the inner scheduler, suspending the task halts the whole subtree.
+pub struct SubSchedulerExecuter<'a, const N: usize, S = ()> {
+    scheduler: Scheduler<'a, N, S>,
+}
+
+/// Gate letting a task run only after another task ran often enough.
+#[derive(Clone, Copy)]
+struct RunGate {
+    producer: TaskId,
+    runs: u64,
+    baseline: u64,
+}
+
+/// Critical section used if no protection is needed.
+struct NoCriticalSection;
+
+/// Fixed size message ring of a task.
+#[derive(Clone, Copy, Default)]
+struct Inbox {
+    messages: [u32; INBOX_SIZE],
+    head: usize,
+    len: usize,
+}
+
+/// Context of a task execution, passed to Execute::execute_with().
+pub struct Context<'c, S = ()> {
+    id: TaskId,
+    inboxes: &'c mut [Inbox],
+    clock: Option<&'c dyn Clock>,
+    started: u64,
+    budget: u64,
+    yield_target: Option<TaskId>,
+    scratch: Option<&'c mut S>,
+    view: Option<SchedulerView<'c>>,
+    deferred: Option<&'c mut Deferred>,
+    cancelled: bool,
 }
 
+/// Queue of the IDs of tasks which deferred their action to the end of
+/// the process cycle, see Context::defer().
+#[derive(Default)]
+struct Deferred {
+    work: [TaskId; DEFER_SIZE],
+    len: usize,
+}
+
+/// Read-only view of the tasks of a scheduler during a task execution,
+/// see Context::view(). The executed task is taken out of its slot while
+/// it runs, so the view never aliases it.
+pub struct SchedulerView<'c> {
+    id: TaskId,
+    slots: &'c dyn TaskSlots,
+}
+
+/// Read access to task slots independent of the scheduler lifetimes.
+trait TaskSlots {
+    fn state_of(&self, id: TaskId) -> Option<TaskState>;
+    fn name_of(&self, id: TaskId) -> Option<&'static str>;
+    fn cpu_share_of(&self, id: TaskId) -> Option<u32>;
+    fn count(&self) -> usize;
+}
+
+/// Task slots of the fixed and overflow store.
+struct Slots<'s, 'a, S> {
+    fixed: &'s [Option<Task<'a, S>>],
+    overflow: &'s [Option<Task<'a, S>>],
+    cpu_times: &'s [u64],
+    cpu_total: u64,
+}
+
+/// Number of messages a task inbox can hold.
+pub const INBOX_SIZE: usize = 4;
+
+/// Number of event IDs of the event bus, see Scheduler::publish().
+pub const EVENT_COUNT: u8 = 32;
+
+/// Number of deferred work items per process cycle.
+pub const DEFER_SIZE: usize = 4;
+
+/// Start value of the FNV-1a hash over the executed task IDs of a cycle.
+const ORDER_HASH_INIT: u64 = 0xcbf2_9ce4_8422_2325;
+
+/// Prime of the FNV-1a hash over the executed task IDs of a cycle.
+const ORDER_HASH_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// Source of the tags identifying scheduler instances.
+static NEXT_TAG: AtomicUsize = AtomicUsize::new(0);
+
+/// State value used by save_states() for empty slots.
+pub const EMPTY_SLOT_STATE: u8 = u8::MAX;
+
 /// Posible error values from this module.
 #[derive(Debug, PartialEq)]
 pub enum Error {
     LimitExceeded,
     NoSuchTaskId,
     InvalidParameter,
+    WrongScheduler,
 }
 
-impl<'a, const SIZE: usize> Scheduler<'a, SIZE> {
-    const TASK_INIT_NONE: Option<Task<'a>> = None;
+impl<'a, const SIZE: usize, S: Default> Scheduler<'a, SIZE, S> {
+    const TASK_INIT_NONE: Option<Task<'a, S>> = None;
 
     /// Creates a scheduler instance with a maximum number of tasks.
     ///
@@ -30,8 +210,52 @@ impl<'a, const SIZE: usize> Scheduler<'a, SIZE> {
     /// let scheduler: Scheduler::<10> = Scheduler::new();  // allow 10 tasks
     /// ```
     pub fn new() -> Self {
-        Scheduler::<SIZE> {
+        Scheduler {
             tasks: [Self::TASK_INIT_NONE; SIZE],
+            deadlock_threshold: None,
+            fairness_state: None,
+            random_state: 0,
+            successors: [INVALID_ID; SIZE],
+            aging_period: None,
+            cycles: 0,
+            budget_cursor: 0,
+            step_mode: false,
+            min_rates: [0; SIZE],
+            last_runs: [0; SIZE],
+            runs: [0; SIZE],
+            run_gates: [None; SIZE],
+            inboxes: [Inbox::default(); SIZE],
+            subscriptions: [0; SIZE],
+            ceilings: [None; SIZE],
+            added: [0; SIZE],
+            generations: [0; SIZE],
+            insertions: 0,
+            reserved: [false; SIZE],
+            yield_clock: None,
+            yield_budget: 0,
+            cpu_clock: None,
+            cpu_times: [0; SIZE],
+            cpu_total: 0,
+            yield_target: None,
+            max_yield_depth: usize::MAX,
+            peak_yield_depth: 0,
+            tag: NEXT_TAG.fetch_add(1, Ordering::Relaxed),
+            scratch: core::array::from_fn(|_| S::default()),
+            frozen: None,
+            enabled_mask: u32::MAX,
+            deferred: Deferred::default(),
+            deferred_actions: core::array::from_fn(|_| None),
+            last_cycle_start: None,
+            jitter: None,
+            eviction_policy: None,
+            order_hash: ORDER_HASH_INIT,
+            cycle_orders: [None; 2],
+            #[cfg(feature = "metrics")]
+            metrics: Metrics::default(),
+            #[cfg(feature = "alloc")]
+            overflow: Vec::new(),
+            #[cfg(feature = "alloc")]
+            overflow_limit: 0,
         }
     }
 
@@ -48,154 +272,4624 @@ impl<'a, const SIZE: usize> Scheduler<'a, SIZE> {
     /// ```
     ///
     pub fn process(&mut self) {
-        for (index, item) in self.tasks.iter_mut().enumerate() {
-            match item {
-                Some(task) => task.process(index),
-                None => (),
+        if self.step_mode {
+            self.step();
+            return;
+        }
+        self.run_cycle(|_| ());
+    }
+
+    /// Enables the step mode for debugging, in which process() runs only
+    /// a single task per call like step().
+    ///
+    pub fn set_step_mode(&mut self, enabled: bool) {
+        self.step_mode = enabled;
+    }
+
+    /// Checks if the step mode is enabled, see set_step_mode().
+    ///
+    pub fn is_step_mode(&self) -> bool {
+        self.step_mode
+    }
+
+    /// Executes the next runnable task after the last one stepped, e.g. to
+    /// single-step through a schedule from a debug console. The cursor is
+    /// shared with process_budget(). Each step counts as a process cycle.
+    /// Returns the ID of the executed task.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lwos::scheduler::Scheduler;
+    ///
+    /// let mut scheduler: Scheduler::<3> = Scheduler::new();
+    /// assert_eq!(scheduler.step(), None);
+    /// ```
+    pub fn step(&mut self) -> Option<TaskId> {
+        let slots = self.slot_count();
+        let mut executed = None;
+        let mut scanned = 0usize;
+
+        while (scanned < slots) && executed.is_none() {
+            let index = (self.budget_cursor + scanned) % slots;
+            scanned += 1;
+
+            if self.execute_slot(index) {
+                executed = Some(index);
             }
         }
+
+        if 0 < slots {
+            self.budget_cursor = (self.budget_cursor + scanned) % slots;
+        }
+
+        self.end_cycle(scanned, usize::from(executed.is_some()));
+
+        executed
     }
 
-    /// Adds a new task to the scheduler.
+    /// Runs a scheduler process cycle like process() and records the time
+    /// since the previous call, measured by `clock`, for cycle_jitter().
     ///
     /// # Examples
     ///
     /// ```
-    /// use lwos::task::{Task, TaskId, TaskState, Execute};
+    /// use lwos::Clock;
     /// use lwos::scheduler::Scheduler;
     ///
-    /// struct SomeExecuter {}
-    /// impl Execute for SomeExecuter {
-    ///     fn execute(&mut self, _id : TaskId) {
+    /// struct Ticks {}
+    /// impl Clock for Ticks {
+    ///     fn now(&self) -> u64 {
+    ///         42
     ///     }
     /// }
     ///
-    /// let mut scheduler: Scheduler::<3> = lwos::Scheduler::new();
-    /// let mut executer = SomeExecuter {};
-    /// let t = lwos::Task::new(lwos::TaskState::Running, &mut executer);
-    /// let task_id = scheduler.add(t).unwrap();
+    /// let mut scheduler: Scheduler::<3> = Scheduler::new();
+    /// scheduler.process_timed(&Ticks {});
+    /// assert_eq!(scheduler.cycle_jitter(), (0, 0, 0));
     /// ```
-    ///   
-    pub fn add(&mut self, task: Task<'a>) -> Result<TaskId, Error> {
-        match self.tasks.iter().position(|x| x.is_none()) {
-            Some(id) => {
-                self.tasks[id] = Some(task);
-                Ok(id)
-            }
-            None => Err(Error::LimitExceeded),
+    pub fn process_timed(&mut self, clock: &dyn Clock) {
+        let now = clock.now();
+
+        if let Some(previous) = self.last_cycle_start {
+            let gap = now.wrapping_sub(previous);
+            self.jitter = Some(match self.jitter {
+                Some((min, max, _)) => (min.min(gap), max.max(gap), gap),
+                None => (gap, gap, gap),
+            });
         }
+        self.last_cycle_start = Some(now);
+
+        self.process();
     }
 
-    /// Removes given task from scheduler.
-    ///  
-    pub fn remove(&mut self, id: TaskId) -> Result<(), Error> {
-        match self.get(id) {
-            Ok(_) => {
-                self.tasks[id] = Self::TASK_INIT_NONE;
-                Ok(())
-            }
-            Err(e) => Err(e),
+    /// Checks if the order of the executed tasks differs between the last
+    /// two process cycles, e.g. to verify a static schedule stays stable or
+    /// to detect starvation in tests. The order is compared by a hash over
+    /// the executed task IDs. Returns false before the second cycle.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lwos::scheduler::Scheduler;
+    ///
+    /// let mut scheduler: Scheduler::<3> = Scheduler::new();
+    /// scheduler.process();
+    /// scheduler.process();
+    /// assert!(!scheduler.order_changed());
+    /// ```
+    pub fn order_changed(&self) -> bool {
+        match self.cycle_orders {
+            [Some(previous), Some(last)] => previous != last,
+            _ => false,
         }
     }
 
-    pub fn get(&mut self, id: TaskId) -> Result<&mut Task<'a>, Error> {
-        if SIZE > id {
-            match &mut self.tasks[id] {
-                Some(task) => Ok(task),
-                None => Err(Error::NoSuchTaskId),
+    /// Gets the minimum, maximum and last time between the starts of two
+    /// successive process_timed() calls, all zero before the second call.
+    ///
+    pub fn cycle_jitter(&self) -> (u64, u64, u64) {
+        self.jitter.unwrap_or((0, 0, 0))
+    }
+
+    /// Runs a scheduler process cycle like process() inside the given
+    /// critical section (requires the `critical-section` feature). Only
+    /// the access to the task states is protected, the tasks execute
+    /// outside of the critical section to keep its duration short.
+    ///
+    #[cfg(feature = "critical-section")]
+    pub fn process_cs(&mut self, cs: &impl CriticalSection) {
+        self.run_cycle_in(cs, |_| ());
+    }
+
+    /// Runs a scheduler process cycle like process() and returns a
+    /// bitmask of the executed tasks, bit i is set if task i executed.
+    /// Only available for schedulers with up to 32 tasks.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lwos::scheduler::Scheduler;
+    ///
+    /// let mut scheduler: Scheduler::<3> = Scheduler::new();
+    /// assert_eq!(scheduler.process_mask(), 0);
+    /// ```
+    pub fn process_mask(&mut self) -> u32 {
+        const { assert!(SIZE <= 32, "process_mask() supports up to 32 tasks") };
+
+        let mut mask = 0u32;
+        self.run_cycle(|id| mask |= 1 << id);
+        mask
+    }
+
+    /// Runs a scheduler process cycle like process() and records the
+    /// executed tasks as `(cycle, TaskId)` pairs into `buf` in execution
+    /// order. Executions beyond the buffer size are not recorded. Returns
+    /// the number of recorded entries, so a replay log is built by calling
+    /// this repeatedly with the remaining part of the buffer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lwos::scheduler::Scheduler;
+    ///
+    /// let mut scheduler: Scheduler::<3> = Scheduler::new();
+    /// let mut log = [(0u64, 0usize); 8];
+    /// assert_eq!(scheduler.record_into(&mut log), 0);
+    /// ```
+    pub fn record_into(&mut self, buf: &mut [(u64, TaskId)]) -> usize {
+        let cycle = self.cycles;
+        let mut count = 0usize;
+
+        self.run_cycle(|id| {
+            if let Some(entry) = buf.get_mut(count) {
+                *entry = (cycle, id);
+                count += 1;
             }
-        } else {
-            Err(Error::InvalidParameter)
+        });
+
+        count
+    }
+
+    /// Gets the number of process cycles run so far.
+    ///
+    pub fn cycles(&self) -> u64 {
+        self.cycles
+    }
+
+    /// Gets the number of cycles run since the given cycle. The counter
+    /// wraps around on overflow, so cycle differences must be taken by
+    /// this instead of comparing cycle values.
+    ///
+    fn cycles_since(&self, cycle: u64) -> u64 {
+        self.cycles.wrapping_sub(cycle)
+    }
+
+    /// Runs process() cycles as long as `cond` returns true, but at most
+    /// `max_cycles` times. The condition is checked before each cycle.
+    /// Returns the number of cycles run.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lwos::scheduler::Scheduler;
+    ///
+    /// let mut scheduler: Scheduler::<3> = Scheduler::new();
+    /// assert_eq!(scheduler.run_while(10, || true), 10);
+    /// assert_eq!(scheduler.run_while(10, || false), 0);
+    /// ```
+    pub fn run_while(&mut self, max_cycles: usize, cond: impl Fn() -> bool) -> usize {
+        let mut count = 0usize;
+
+        while (count < max_cycles) && cond() {
+            self.process();
+            count += 1;
         }
+
+        count
     }
 
-    /// Gets the maximum number of tasks supported by this scheduler.
+    /// Runs a budgeted process cycle executing at most `budget` tasks. The
+    /// next call continues with the task following the last one scanned,
+    /// so all tasks get their turn over several calls. Tasks with a
+    /// minimum rate (see set_min_rate()) which would miss their rate are
+    /// executed first. Returns the number of executed tasks.
     ///
     /// # Examples
     ///
     /// ```
     /// use lwos::scheduler::Scheduler;
     ///
-    /// let scheduler: Scheduler::<3> = Scheduler::new();
-    /// assert_eq!(scheduler.capacity(), 3);
+    /// let mut scheduler: Scheduler::<3> = Scheduler::new();
+    /// assert_eq!(scheduler.process_budget(2), 0);
     /// ```
-    pub const fn capacity(&self) -> usize {
-        SIZE
+    pub fn process_budget(&mut self, budget: usize) -> usize {
+        let mut done = [false; SIZE];
+        let mut executions = 0usize;
+
+        for (id, done) in done.iter_mut().enumerate() {
+            if executions >= budget {
+                break;
+            }
+
+            let rate = self.min_rates[id];
+            if (0 < rate)
+                && (self.cycles_since(self.last_runs[id]) >= rate)
+                && self.execute_slot(id)
+            {
+                *done = true;
+                executions += 1;
+            }
+        }
+
+        let slots = self.slot_count();
+        let mut scanned = 0usize;
+
+        while (scanned < slots) && (executions < budget) {
+            let index = (self.budget_cursor + scanned) % slots;
+            scanned += 1;
+
+            if !done.get(index).copied().unwrap_or(false) && self.execute_slot(index) {
+                executions += 1;
+            }
+        }
+
+        if 0 < slots {
+            self.budget_cursor = (self.budget_cursor + scanned) % slots;
+        }
+
+        self.end_cycle(scanned, executions);
+
+        executions
     }
-}
 
-impl<'a, const SIZE: usize> Default for Scheduler<'a, SIZE> {
-    fn default() -> Self {
-        Self::new()
+    /// Gets the number of executions of a task of the fixed store.
+    ///
+    pub fn runs(&self, id: TaskId) -> Result<u64, Error> {
+        self.get_ref(id)?;
+        self.runs.get(id).copied().ok_or(Error::InvalidParameter)
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    struct SomeExecuter {}
-    impl Execute for SomeExecuter {
-        fn execute(&mut self, _id: TaskId) {}
+    /// Lets task `consumer` execute only after task `producer` executed at
+    /// least `runs` times since the last execution of the consumer. A
+    /// count of zero removes the gate. Only tasks of the fixed store are
+    /// supported.
+    ///
+    pub fn wait_for_runs(
+        &mut self,
+        consumer: TaskId,
+        producer: TaskId,
+        runs: u64,
+    ) -> Result<(), Error> {
+        self.get(consumer)?;
+        self.get(producer)?;
+
+        if (SIZE <= consumer) || (SIZE <= producer) {
+            return Err(Error::InvalidParameter);
+        }
+
+        self.run_gates[consumer] = (0 < runs).then_some(RunGate {
+            producer,
+            runs,
+            baseline: self.runs[producer],
+        });
+
+        Ok(())
     }
 
-    #[test]
-    fn scheduler_add() {
-        let mut scheduler: Scheduler<1> = Scheduler::new();
-        assert!(scheduler.tasks[0].is_none());
+    /// Requests a task to be executed at least once every `cycles` cycles
+    /// of process_budget(). A rate of zero removes the requirement. Only
+    /// tasks in the fixed store (IDs below the capacity) support a rate.
+    ///
+    pub fn set_min_rate(&mut self, id: TaskId, cycles: u64) -> Result<(), Error> {
+        self.get(id)?;
 
-        let mut e1: SomeExecuter = SomeExecuter {};
-        let t1 = Task::new(TaskState::Running, &mut e1);
+        if SIZE <= id {
+            return Err(Error::InvalidParameter);
+        }
 
-        assert_eq!(scheduler.add(t1).unwrap(), 0);
+        self.min_rates[id] = cycles;
+        self.last_runs[id] = self.cycles;
 
-        assert_eq!(
-            scheduler.tasks[0].as_ref().unwrap().state,
-            TaskState::Running
-        );
+        Ok(())
     }
 
-    #[test]
-    fn scheduler_add_capacity() {
-        let mut scheduler: Scheduler<3> = Scheduler::new();
+    /// Runs a priority scheduling cycle by executing only the running task
+    /// with the highest effective priority. On equal priorities the task
+    /// with the lower ID wins. Tasks in other states are not processed by
+    /// this cycle, neither are tasks waiting for a run gate (see
+    /// wait_for_runs()). Returns the ID of the executed task.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lwos::scheduler::Scheduler;
+    ///
+    /// let mut scheduler: Scheduler::<3> = Scheduler::new();
+    /// assert_eq!(scheduler.process_priority(), None);
+    /// ```
+    pub fn process_priority(&mut self) -> Option<TaskId> {
+        let mut selected: Option<(TaskId, u8)> = None;
 
-        let mut e1: SomeExecuter = SomeExecuter {};
-        let mut e2: SomeExecuter = SomeExecuter {};
-        let mut e3: SomeExecuter = SomeExecuter {};
-        let mut e4: SomeExecuter = SomeExecuter {};
+        for (index, task) in self.iter() {
+            let priority = self.effective_priority(index, task);
 
-        let t1 = Task::new(TaskState::Running, &mut e1);
-        let t2 = Task::new(TaskState::Running, &mut e2);
-        let t3 = Task::new(TaskState::Running, &mut e3);
-        let t4 = Task::new(TaskState::Running, &mut e4);
+            if (TaskState::Running == task.state)
+                && self.enabled(index)
+                && self.gate_open(index)
+                && !self.ceiling_blocks(index)
+                && selected.is_none_or(|(_, best)| priority > best)
+            {
+                selected = Some((index, priority));
+            }
+        }
 
-        assert_eq!(scheduler.add(t1).unwrap(), 0);
-        assert_eq!(scheduler.add(t2).unwrap(), 1);
-        assert_eq!(scheduler.add(t3).unwrap(), 2);
-        assert_eq!(scheduler.add(t4).unwrap_err(), Error::LimitExceeded);
+        let (id, _) = selected?;
+
+        if let Some(period) = self.aging_period {
+            for index in 0..self.slot_count() {
+                if let Some(Some(task)) = self.slot_mut(index) {
+                    if (index != id) && (TaskState::Running == task.state) {
+                        task.age(period);
+                    }
+                }
+            }
+        }
+
+        let executed = self.execute_slot(id);
+        self.end_cycle(self.slot_count(), usize::from(executed));
+
+        executed.then_some(id)
     }
 
-    #[test]
-    #[should_panic]
-    fn scheduler_add_too_much() {
-        let mut scheduler: Scheduler<1> = Scheduler::new();
-        let mut e1: SomeExecuter = SomeExecuter {};
-        let mut e2: SomeExecuter = SomeExecuter {};
-        let t1 = Task::new(TaskState::Running, &mut e1);
-        let t2 = Task::new(TaskState::Running, &mut e2);
+    /// Runs a process cycle executing the tasks grouped by priority, from
+    /// the highest to the lowest level. Within a level the tasks execute
+    /// round robin, the first task of a level rotates with each cycle.
+    /// Tasks of the overflow store execute last in ID order. Returns the
+    /// number of executed tasks.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lwos::scheduler::Scheduler;
+    ///
+    /// let mut scheduler: Scheduler::<3> = Scheduler::new();
+    /// assert_eq!(scheduler.process_grouped(), 0);
+    /// ```
+    pub fn process_grouped(&mut self) -> usize {
+        let mut executions = 0usize;
+        let mut level = self.tasks.iter().flatten().map(|t| t.priority).max();
 
-        assert_eq!(scheduler.add(t1).unwrap(), 0);
-        assert_eq!(scheduler.add(t2).unwrap(), 1); // <- panics (capacity)
+        while let Some(priority) = level {
+            let mut members = [INVALID_ID; SIZE];
+            let mut count = 0usize;
+
+            for (id, slot) in self.tasks.iter().enumerate() {
+                if slot.as_ref().is_some_and(|t| priority == t.priority) {
+                    members[count] = id;
+                    count += 1;
+                }
+            }
+
+            let start = (self.cycles % count as u64) as usize;
+            for offset in 0..count {
+                if self.execute_slot(members[(start + offset) % count]) {
+                    executions += 1;
+                }
+            }
+
+            level = self
+                .tasks
+                .iter()
+                .flatten()
+                .map(|t| t.priority)
+                .filter(|&p| p < priority)
+                .max();
+        }
+
+        for index in SIZE..self.slot_count() {
+            if self.execute_slot(index) {
+                executions += 1;
+            }
+        }
+
+        self.end_cycle(self.slot_count(), executions);
+
+        executions
     }
 
-    #[test]
-    fn scheduler_remove() {
-        let mut scheduler: Scheduler<1> = Scheduler::new();
-        let mut e1: SomeExecuter = SomeExecuter {};
-        let t1 = Task::new(TaskState::Running, &mut e1);
+    /// Runs a process cycle executing only the tasks of the given phase,
+    /// see Task::phase. A main loop with distinct phases like input,
+    /// compute and output calls this once per phase. Each call counts as
+    /// a process cycle. Returns the number of executed tasks.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lwos::scheduler::Scheduler;
+    ///
+    /// let mut scheduler: Scheduler::<3> = Scheduler::new();
+    /// assert_eq!(scheduler.process_phase(0), 0);
+    /// ```
+    pub fn process_phase(&mut self, phase: u8) -> usize {
+        let mut executions = 0usize;
 
-        assert_eq!(scheduler.add(t1).unwrap(), 0);
-        assert_eq!(scheduler.remove(0), Ok(()));
+        for index in 0..self.slot_count() {
+            let member = self
+                .slot(index)
+                .is_some_and(|slot| slot.as_ref().is_some_and(|t| phase == t.phase));
+
+            if member && self.execute_slot(index) {
+                executions += 1;
+            }
+        }
+
+        self.end_cycle(self.slot_count(), executions);
+
+        executions
+    }
+
+    /// Runs the initialization phase by executing each running task with
+    /// Task::init set once, from the highest to the lowest priority and on
+    /// equal priorities by ID. Init tasks get a single chance, their init
+    /// flag is cleared and, if they are still running afterwards, they are
+    /// suspended. The phase doesn't count as process cycle. Returns the
+    /// number of executed tasks.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lwos::scheduler::Scheduler;
+    ///
+    /// let mut scheduler: Scheduler::<3> = Scheduler::new();
+    /// assert_eq!(scheduler.run_init_phase(), 0);
+    /// ```
+    pub fn run_init_phase(&mut self) -> usize {
+        let mut executions = 0usize;
+
+        loop {
+            let mut selected: Option<(TaskId, u8)> = None;
+
+            for (index, task) in self.iter() {
+                if task.init
+                    && (TaskState::Running == task.state)
+                    && selected.is_none_or(|(_, best)| task.priority > best)
+                {
+                    selected = Some((index, task.priority));
+                }
+            }
+
+            let Some((id, _)) = selected else {
+                break;
+            };
+
+            if let Some(Some(task)) = self.slot_mut(id) {
+                task.init = false;
+            }
+
+            if self.execute_slot(id) {
+                executions += 1;
+
+                if let Some(Some(task)) = self.slot_mut(id) {
+                    if TaskState::Running == task.state {
+                        task.state = TaskState::Suspended;
+                    }
+                }
+            }
+        }
+        self.order_hash = ORDER_HASH_INIT;
+
+        executions
+    }
+
+    /// Sets the enable mask of the tasks, bit i enables task i. Process
+    /// cycles treat disabled tasks as suspended without changing their
+    /// state, so operating modes are switched by a single write. Tasks with
+    /// IDs of 32 and above are always enabled. All tasks are enabled by
+    /// default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lwos::scheduler::Scheduler;
+    ///
+    /// let mut scheduler: Scheduler::<3> = Scheduler::new();
+    /// scheduler.set_enabled_mask(0b101);
+    /// assert_eq!(scheduler.enabled_mask(), 0b101);
+    /// ```
+    pub fn set_enabled_mask(&mut self, mask: u32) {
+        self.enabled_mask = mask;
+    }
+
+    /// Gets the enable mask of the tasks, see set_enabled_mask().
+    ///
+    pub fn enabled_mask(&self) -> u32 {
+        self.enabled_mask
+    }
+
+    /// Freezes the execution order of the current task set for
+    /// process_frozen(). The tasks are ordered by priority, from high to
+    /// low, and by ID within a priority. Adding, removing or compacting
+    /// tasks invalidates the order, changing priorities requires another
+    /// call of freeze().
+    ///
+    pub fn freeze(&mut self) {
+        let mut order = [INVALID_ID; SIZE];
+        let mut count = 0usize;
+
+        for (id, slot) in self.tasks.iter().enumerate() {
+            if slot.is_some() {
+                order[count] = id;
+                count += 1;
+            }
+        }
+
+        let tasks = &self.tasks;
+        order[..count].sort_unstable_by_key(|&id| {
+            let priority = tasks[id].as_ref().map_or(0, |t| t.priority);
+            (core::cmp::Reverse(priority), id)
+        });
+        self.frozen = Some(order);
+    }
+
+    /// Checks if the execution order is frozen, see freeze().
+    ///
+    pub fn is_frozen(&self) -> bool {
+        self.frozen.is_some()
+    }
+
+    /// Runs a process cycle executing the tasks in the order computed by
+    /// freeze(), without ordering them again. An invalidated order gets
+    /// frozen again first. Tasks of the overflow store execute last in ID
+    /// order. Returns the number of executed tasks.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lwos::scheduler::Scheduler;
+    ///
+    /// let mut scheduler: Scheduler::<3> = Scheduler::new();
+    /// scheduler.freeze();
+    /// assert_eq!(scheduler.process_frozen(), 0);
+    /// ```
+    pub fn process_frozen(&mut self) -> usize {
+        let mut executions = 0usize;
+
+        if self.frozen.is_none() {
+            self.freeze();
+        }
+        let order = self.frozen.unwrap_or([INVALID_ID; SIZE]);
+
+        for &id in order.iter().take_while(|&&id| INVALID_ID != id) {
+            if self.execute_slot(id) {
+                executions += 1;
+            }
+        }
+
+        for index in SIZE..self.slot_count() {
+            if self.execute_slot(index) {
+                executions += 1;
+            }
+        }
+
+        self.end_cycle(self.slot_count(), executions);
+
+        executions
+    }
+
+    /// Enables priority aging for process_priority(). A running task which
+    /// is not selected gains one priority level every `period` cycles
+    /// until it gets executed.
+    ///
+    pub fn enable_aging(&mut self, period: usize) {
+        self.aging_period = Some(period.max(1));
+    }
+
+    /// Disables priority aging.
+    ///
+    pub fn disable_aging(&mut self) {
+        self.aging_period = None;
+    }
+
+    /// Executes all tasks once and calls `on_executed` for each task
+    /// which was executed.
+    ///
+    fn run_cycle(&mut self, on_executed: impl FnMut(TaskId)) {
+        self.run_cycle_in(&NoCriticalSection, on_executed);
+    }
+
+    /// Executes all tasks once like run_cycle(), with the task states
+    /// accessed inside the given critical section.
+    ///
+    fn run_cycle_in(&mut self, cs: &impl CriticalSection, mut on_executed: impl FnMut(TaskId)) {
+        let start = self.next_start();
+        let mut executions = 0usize;
+        let mut done = [false; SIZE];
+
+        for offset in 0..SIZE {
+            let mut next = Some((start + offset) % SIZE);
+            let mut depth = 0usize;
+
+            while let Some(index) = next.take() {
+                if done[index] {
+                    break;
+                }
+                done[index] = true;
+
+                if self.execute_slot_in(index, cs) {
+                    on_executed(index);
+                    executions += 1;
+                    next = self
+                        .yield_target
+                        .filter(|&target| (SIZE > target) && (depth < self.max_yield_depth));
+
+                    if next.is_some() {
+                        depth += 1;
+                        self.peak_yield_depth = self.peak_yield_depth.max(depth);
+                    }
+                }
+            }
+        }
+
+        for index in SIZE..self.slot_count() {
+            if self.execute_slot_in(index, cs) {
+                on_executed(index);
+                executions += 1;
+            }
+        }
+
+        self.end_cycle(self.slot_count(), executions);
+    }
+
+    /// Finishes a process cycle by accounting it and running the work
+    /// deferred by the tasks.
+    ///
+    fn end_cycle(&mut self, scans: usize, executions: usize) {
+        self.record_cycle(scans, executions);
+        self.cycles = self.cycles.wrapping_add(1);
+        self.cycle_orders = [self.cycle_orders[1], Some(self.order_hash)];
+        self.order_hash = ORDER_HASH_INIT;
+
+        let deferred = core::mem::take(&mut self.deferred);
+        for id in deferred.work.iter().take(deferred.len) {
+            if let Some(Some(action)) = self.deferred_actions.get_mut(*id) {
+                action();
+            }
+        }
+    }
+
+    /// Accounts a process cycle in the instrumentation counters. This is a
+    /// no-op without the `metrics` feature.
+    ///
+    #[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+    fn record_cycle(&mut self, scans: usize, executions: usize) {
+        #[cfg(feature = "metrics")]
+        self.metrics.record_cycle(scans, executions);
+    }
+
+    /// Processes the task in the given slot, if any, and handles its
+    /// execution result. Returns true if the task was executed.
+    ///
+    fn execute_slot(&mut self, id: TaskId) -> bool {
+        self.execute_slot_in(id, &NoCriticalSection)
+    }
+
+    /// Processes the task in the given slot like execute_slot(). The task
+    /// state is accessed inside the critical section, while the task
+    /// itself executes outside of it.
+    ///
+    fn execute_slot_in(&mut self, id: TaskId, cs: &impl CriticalSection) -> bool {
+        if !self.enabled(id) || !self.gate_open(id) {
+            return false;
+        }
+
+        let ready = cs.with(|| {
+            self.slot_mut(id)
+                .and_then(|slot| slot.as_mut())
+                .is_some_and(Task::prepare)
+        });
+        if !ready {
+            return false;
+        }
+
+        let Some(mut task) = self.slot_mut(id).and_then(Option::take) else {
+            return false;
+        };
+
+        #[cfg(feature = "alloc")]
+        let overflow = self.overflow.as_slice();
+        #[cfg(not(feature = "alloc"))]
+        let overflow = &[];
+        let slots = Slots {
+            fixed: &self.tasks,
+            overflow,
+            cpu_times: &self.cpu_times,
+            cpu_total: self.cpu_total,
+        };
+
+        let mut ctx = Context::new(id, &mut self.inboxes);
+        ctx.view = Some(SchedulerView { id, slots: &slots });
+        ctx.deferred = Some(&mut self.deferred);
+        ctx.cancelled = task.is_cancelled();
+        if let Some(clock) = self.yield_clock {
+            ctx.start_budget(clock, self.yield_budget);
+        }
+        if let Some(scratch) = self.scratch.get_mut(id) {
+            ctx.scratch = Some(scratch);
+        }
+        let started = self.cpu_clock.map(|clock| clock.now());
+        let control = task.run(id, &mut ctx);
+        self.yield_target = ctx.yield_target;
+        if let (Some(clock), Some(started)) = (self.cpu_clock, started) {
+            if let Some(cpu_time) = self.cpu_times.get_mut(id) {
+                let elapsed = clock.now().wrapping_sub(started);
+                *cpu_time = cpu_time.saturating_add(elapsed);
+                self.cpu_total = self.cpu_total.saturating_add(elapsed);
+            }
+        }
+
+        cs.with(|| {
+            if let Some(slot) = self.slot_mut(id) {
+                *slot = Some(task);
+            }
+            if let Some(last_run) = self.last_runs.get_mut(id) {
+                *last_run = self.cycles;
+            }
+            if let Some(runs) = self.runs.get_mut(id) {
+                *runs = runs.wrapping_add(1);
+            }
+            self.order_hash = (self.order_hash ^ id as u64).wrapping_mul(ORDER_HASH_PRIME);
+            if let Some(Some(gate)) = self.run_gates.get(id).copied() {
+                self.run_gates[id] = Some(RunGate {
+                    baseline: self.runs[gate.producer],
+                    ..gate
+                });
+            }
+
+            if TaskControl::Remove == control {
+                self.complete(id);
+            }
+        });
+
+        true
+    }
+
+    /// Checks if the run gate of a task (see wait_for_runs()) is open.
+    ///
+    fn gate_open(&self, id: TaskId) -> bool {
+        match self.run_gates.get(id).copied().flatten() {
+            Some(gate) => self.runs[gate.producer].wrapping_sub(gate.baseline) >= gate.runs,
+            None => true,
+        }
+    }
+
+    /// Checks if a task is blocked by the priority ceiling protocol, i.e.
+    /// another task is in a critical section with a ceiling not below the
+    /// priority of the task, see enter_critical(). Ceilings of tasks which
+    /// can't run, e.g. suspended, faulted, waiting or delayed ones, are
+    /// ignored, as process_priority() would never pick them to leave their
+    /// critical section.
+    ///
+    fn ceiling_blocks(&self, id: TaskId) -> bool {
+        let Some(Some(task)) = self.slot(id) else {
+            return false;
+        };
+        if self.ceilings.get(id).copied().flatten().is_some() {
+            return false;
+        }
+
+        self.ceilings
+            .iter()
+            .enumerate()
+            .filter_map(|(holder, ceiling)| ceiling.filter(|_| self.can_run(holder)))
+            .max()
+            .is_some_and(|ceiling| task.effective_priority() <= ceiling)
+    }
+
+    /// Checks if process_priority() may pick a task, apart from priorities.
+    ///
+    fn can_run(&self, id: TaskId) -> bool {
+        matches!(self.slot(id), Some(Some(task)) if TaskState::Running == task.state)
+            && self.enabled(id)
+            && self.gate_open(id)
+    }
+
+    /// Gets the effective priority of a task (see Task::effective_priority())
+    /// raised to the ceiling of its critical section, see enter_critical().
+    ///
+    fn effective_priority(&self, id: TaskId, task: &Task<S>) -> u8 {
+        let priority = task.effective_priority();
+
+        match self.ceilings.get(id).copied().flatten() {
+            Some(ceiling) => priority.max(ceiling),
+            None => priority,
+        }
+    }
+
+    /// Checks if a task is enabled by the enable mask. Tasks with IDs
+    /// beyond the mask are always enabled.
+    ///
+    fn enabled(&self, id: TaskId) -> bool {
+        u32::try_from(id)
+            .ok()
+            .and_then(|bit| self.enabled_mask.checked_shr(bit))
+            .is_none_or(|mask| 0 != mask & 1)
+    }
+
+    /// Removes a task which completed and resumes its successor.
+    ///
+    fn complete(&mut self, id: TaskId) {
+        let successor = self.successors.get(id).copied().unwrap_or(INVALID_ID);
+
+        if self.remove(id).is_ok() {
+            if let Ok(task) = self.get(successor) {
+                task.resume();
+            }
+        }
+    }
+
+    /// Links two tasks so that task `next` is resumed once task `id`
+    /// completes by returning `TaskControl::Remove`. A task has at most
+    /// one successor, linking again replaces the previous one. Only tasks
+    /// in the fixed store (IDs below the capacity) can have a successor.
+    ///
+    pub fn on_complete(&mut self, id: TaskId, next: TaskId) -> Result<(), Error> {
+        self.get(next)?;
+        self.get(id)?;
+
+        match self.successors.get_mut(id) {
+            Some(successor) => *successor = next,
+            None => return Err(Error::InvalidParameter),
+        }
+
+        Ok(())
+    }
+
+    /// Lets task `id` enter a critical section on a shared resource with
+    /// the given priority ceiling, i.e. the highest priority of all tasks
+    /// using the resource. Until exit_critical(), the task runs with at
+    /// least the ceiling priority and all other tasks with a priority up to
+    /// the ceiling are blocked, so they can't take turns inside the
+    /// critical section. While the task can't run, e.g. when suspended or
+    /// faulted, its ceiling blocks nobody. Ceilings only apply to
+    /// process_priority(), the other process variants ignore priorities.
+    /// Only tasks of the fixed store are supported.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lwos::scheduler::{Error, Scheduler};
+    ///
+    /// let mut scheduler: Scheduler::<3> = Scheduler::new();
+    /// assert_eq!(scheduler.enter_critical(0, 5), Err(Error::NoSuchTaskId));
+    /// ```
+    pub fn enter_critical(&mut self, id: TaskId, ceiling: u8) -> Result<(), Error> {
+        self.get(id)?;
+        *self.ceilings.get_mut(id).ok_or(Error::InvalidParameter)? = Some(ceiling);
+        Ok(())
+    }
+
+    /// Lets task `id` leave its critical section, see enter_critical().
+    ///
+    pub fn exit_critical(&mut self, id: TaskId) -> Result<(), Error> {
+        self.get(id)?;
+        *self.ceilings.get_mut(id).ok_or(Error::InvalidParameter)? = None;
+        Ok(())
+    }
+
+    /// Sets the action task `id` defers to the end of a process cycle by
+    /// Context::defer(). The action is a closure borrowed for the lifetime
+    /// of the scheduler, so it can carry its own state. Only tasks of the
+    /// fixed store are supported.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lwos::scheduler::{Error, Scheduler};
+    ///
+    /// let mut cleanup = || ();
+    /// let mut scheduler: Scheduler::<3> = Scheduler::new();
+    /// assert_eq!(
+    ///     scheduler.set_deferred_action(0, &mut cleanup),
+    ///     Err(Error::NoSuchTaskId)
+    /// );
+    /// ```
+    pub fn set_deferred_action(
+        &mut self,
+        id: TaskId,
+        action: &'a mut dyn FnMut(),
+    ) -> Result<(), Error> {
+        self.get(id)?;
+        *self
+            .deferred_actions
+            .get_mut(id)
+            .ok_or(Error::InvalidParameter)? = Some(action);
+        Ok(())
+    }
+
+    /// Seeds a pseudo random start position for each process cycle. By
+    /// default, each cycle starts with the first task. With a seed, the
+    /// cycle starts at a task chosen by a linear congruential generator,
+    /// so the start order varies but is reproducible for a given seed.
+    ///
+    pub fn set_fairness_seed(&mut self, seed: u32) {
+        self.fairness_state = Some(u64::from(seed));
+    }
+
+    /// Seeds the pseudo random selection of process_random(). The seed is
+    /// zero by default.
+    ///
+    pub fn set_random_seed(&mut self, seed: u32) {
+        self.random_state = u64::from(seed);
+    }
+
+    /// Runs a process cycle executing a single running task, selected
+    /// pseudo randomly with a weight of its priority plus one, e.g. for
+    /// chaos testing of tasks depending on the execution order. The
+    /// selection is reproducible for a given seed, see set_random_seed().
+    /// Returns the ID of the executed task.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lwos::scheduler::Scheduler;
+    ///
+    /// let mut scheduler: Scheduler::<3> = Scheduler::new();
+    /// scheduler.set_random_seed(42);
+    /// assert_eq!(scheduler.process_random(), None);
+    /// ```
+    pub fn process_random(&mut self) -> Option<TaskId> {
+        let weight_of = |task: &Task<S>| u32::from(task.priority) + 1;
+        let runnable = |id: TaskId, task: &Task<S>| {
+            (TaskState::Running == task.state) && self.enabled(id) && self.gate_open(id)
+        };
+
+        let total: u32 = self
+            .iter()
+            .filter(|(id, task)| runnable(*id, task))
+            .map(|(_, task)| weight_of(task))
+            .sum();
+
+        let state = lcg_next(self.random_state);
+        let mut selected = None;
+        if 0 < total {
+            let mut pick = lcg_output(state) % total;
+
+            for (id, task) in self.iter().filter(|(id, task)| runnable(*id, task)) {
+                let weight = weight_of(task);
+                if pick < weight {
+                    selected = Some(id);
+                    break;
+                }
+                pick -= weight;
+            }
+        }
+
+        if selected.is_some() {
+            self.random_state = state;
+        }
+
+        let executed = selected.filter(|id| self.execute_slot(*id));
+        self.end_cycle(self.slot_count(), usize::from(executed.is_some()));
+
+        executed
+    }
+
+    /// Gets the slot index the next process cycle starts with.
+    ///
+    fn next_start(&mut self) -> usize {
+        match self.fairness_state {
+            Some(state) if 0 < SIZE => {
+                let state = lcg_next(state);
+                self.fairness_state = Some(state);
+                lcg_output(state) as usize % SIZE
+            }
+            _ => 0,
+        }
+    }
+
+    /// Adds a new task to the scheduler.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lwos::task::{Task, TaskId, TaskState, Execute, TaskControl};
+    /// use lwos::scheduler::Scheduler;
+    ///
+    /// struct SomeExecuter {}
+    /// impl Execute for SomeExecuter {
+    ///     fn execute(&mut self, _id : TaskId) -> TaskControl {
+    ///         TaskControl::Continue
+    ///     }
+    /// }
+    ///
+    /// let mut scheduler: Scheduler::<3> = lwos::Scheduler::new();
+    /// let mut executer = SomeExecuter {};
+    /// let t = lwos::Task::new(lwos::TaskState::Running, &mut executer);
+    /// let task_id = scheduler.add(t).unwrap();
+    /// ```
+    ///   
+    pub fn add(&mut self, task: Task<'a, S>) -> Result<TaskId, Error> {
+        match self
+            .tasks
+            .iter()
+            .zip(self.reserved.iter())
+            .position(|(x, reserved)| x.is_none() && !reserved)
+        {
+            Some(id) => {
+                self.tasks[id] = Some(task);
+                self.frozen = None;
+                self.stamp(id);
+                Ok(id)
+            }
+            None => self.add_overflow(task).or_else(|task| self.evict_for(task)),
+        }
+    }
+
+    /// Adds a task with the given ID, which may be a reserved one. Only
+    /// IDs of the fixed store are supported.
+    ///
+    pub fn add_at(&mut self, id: TaskId, task: Task<'a, S>) -> Result<(), Error> {
+        match self.tasks.get_mut(id) {
+            Some(slot) if slot.is_none() => {
+                *slot = Some(task);
+                self.frozen = None;
+                self.stamp(id);
+                Ok(())
+            }
+            _ => Err(Error::InvalidParameter),
+        }
+    }
+
+    /// Reserves a task ID, so add() doesn't pick it. The ID stays reserved
+    /// until release() is called, tasks get added to it by add_at().
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lwos::scheduler::Scheduler;
+    ///
+    /// let mut scheduler: Scheduler::<3> = Scheduler::new();
+    /// assert_eq!(scheduler.reserve(1), Ok(()));
+    /// assert_eq!(scheduler.release(1), Ok(()));
+    /// ```
+    pub fn reserve(&mut self, id: TaskId) -> Result<(), Error> {
+        match self.reserved.get_mut(id) {
+            Some(reserved) => {
+                *reserved = true;
+                Ok(())
+            }
+            None => Err(Error::InvalidParameter),
+        }
+    }
+
+    /// Releases a reserved task ID, so add() may pick it again.
+    ///
+    pub fn release(&mut self, id: TaskId) -> Result<(), Error> {
+        match self.reserved.get_mut(id) {
+            Some(reserved) => {
+                *reserved = false;
+                Ok(())
+            }
+            None => Err(Error::InvalidParameter),
+        }
+    }
+
+    /// Adds a task to the overflow store if the fixed store is full. The
+    /// task is given back if the overflow store is full as well.
+    ///
+    #[cfg(feature = "alloc")]
+    fn add_overflow(&mut self, task: Task<'a, S>) -> Result<TaskId, Task<'a, S>> {
+        if let Some(index) = self.overflow.iter().position(|x| x.is_none()) {
+            self.overflow[index] = Some(task);
+            Ok(SIZE + index)
+        } else if self.overflow.len() < self.overflow_limit {
+            self.overflow.push(Some(task));
+            Ok(SIZE + self.overflow.len() - 1)
+        } else {
+            Err(task)
+        }
+    }
+
+    #[cfg(not(feature = "alloc"))]
+    fn add_overflow(&mut self, task: Task<'a, S>) -> Result<TaskId, Task<'a, S>> {
+        Err(task)
+    }
+
+    /// Sets the policy called by add() if the scheduler is full. The policy
+    /// returns the ID of a task to evict, e.g. the one with the lowest
+    /// priority, and the new task takes over its ID. If it returns None,
+    /// add() fails with `Error::LimitExceeded` as without a policy.
+    ///
+    pub fn set_eviction_policy(&mut self, policy: EvictionPolicy<'a, SIZE, S>) {
+        self.eviction_policy = Some(policy);
+    }
+
+    /// Evicts the task chosen by the eviction policy and adds `task` in
+    /// its place.
+    ///
+    fn evict_for(&mut self, task: Task<'a, S>) -> Result<TaskId, Error> {
+        let policy = self.eviction_policy.take().ok_or(Error::LimitExceeded)?;
+        let victim = policy(self);
+        self.eviction_policy = Some(policy);
+
+        let id = victim.ok_or(Error::LimitExceeded)?;
+        self.remove(id).map_err(|_| Error::LimitExceeded)?;
+        if let Some(slot) = self.slot_mut(id) {
+            *slot = Some(task);
+        }
+        self.stamp(id);
+        Ok(id)
+    }
+
+    /// Records the insertion order of the task added to slot `id` of the
+    /// fixed store, see clear_ordered().
+    ///
+    fn stamp(&mut self, id: TaskId) {
+        if let Some(added) = self.added.get_mut(id) {
+            self.insertions = self.insertions.wrapping_add(1);
+            *added = self.insertions;
+        }
+    }
+
+    /// Removes all tasks in reverse insertion order and calls `teardown`
+    /// for each task before removing it, e.g. to release resources in the
+    /// reverse order of their dependencies. Tasks of the overflow store
+    /// go first in reverse ID order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lwos::scheduler::Scheduler;
+    ///
+    /// let mut scheduler: Scheduler::<3> = Scheduler::new();
+    /// scheduler.clear_ordered(|_id, _task| {});
+    /// assert!(scheduler.is_empty());
+    /// ```
+    pub fn clear_ordered(&mut self, mut teardown: impl FnMut(TaskId, &mut Task<'a, S>)) {
+        for id in (SIZE..self.slot_count()).rev() {
+            if let Ok(task) = self.get(id) {
+                teardown(id, task);
+                let _ = self.remove(id);
+            }
+        }
+
+        while let Some(id) = (0..SIZE)
+            .filter(|id| self.tasks[*id].is_some())
+            .max_by_key(|id| self.added[*id])
+        {
+            if let Ok(task) = self.get(id) {
+                teardown(id, task);
+            }
+            let _ = self.remove(id);
+        }
+    }
+
+    /// Allows `limit` tasks to be added beyond the capacity (requires the
+    /// `alloc` feature). These tasks are stored on the heap and get IDs
+    /// starting at the capacity. The default limit is zero.
+    ///
+    #[cfg(feature = "alloc")]
+    pub fn set_overflow_limit(&mut self, limit: usize) {
+        self.overflow_limit = limit;
+    }
+
+    /// Adds a new task which starts running after the given number of
+    /// process cycles, i.e. it executes first in cycle `delay_cycles + 1`.
+    ///
+    pub fn add_delayed(
+        &mut self,
+        mut task: Task<'a, S>,
+        delay_cycles: usize,
+    ) -> Result<TaskId, Error> {
+        task.delay(delay_cycles);
+        self.add(task)
+    }
+
+    /// Removes given task from scheduler.
+    ///  
+    pub fn remove(&mut self, id: TaskId) -> Result<(), Error> {
+        match self.get(id) {
+            Ok(_) => {
+                if let Some(slot) = self.slot_mut(id) {
+                    *slot = Self::TASK_INIT_NONE;
+                }
+                self.bump_generation(id);
+                self.frozen = None;
+                if let Some(successor) = self.successors.get_mut(id) {
+                    *successor = INVALID_ID;
+                }
+                if let Some(rate) = self.min_rates.get_mut(id) {
+                    *rate = 0;
+                }
+                if let Some(inbox) = self.inboxes.get_mut(id) {
+                    *inbox = Inbox::default();
+                }
+                if let Some(events) = self.subscriptions.get_mut(id) {
+                    *events = 0;
+                }
+                if let Some(ceiling) = self.ceilings.get_mut(id) {
+                    *ceiling = None;
+                }
+                if let Some(action) = self.deferred_actions.get_mut(id) {
+                    *action = None;
+                }
+                if let Some(cpu_time) = self.cpu_times.get_mut(id) {
+                    *cpu_time = 0;
+                }
+                if let Some(scratch) = self.scratch.get_mut(id) {
+                    *scratch = S::default();
+                }
+                if let Some(runs) = self.runs.get_mut(id) {
+                    *runs = 0;
+                }
+                if let Some(gate) = self.run_gates.get_mut(id) {
+                    *gate = None;
+                }
+                for successor in self.successors.iter_mut() {
+                    if id == *successor {
+                        *successor = INVALID_ID;
+                    }
+                }
+                for gate in self.run_gates.iter_mut() {
+                    if gate.is_some_and(|gate| id == gate.producer) {
+                        *gate = None;
+                    }
+                }
+                Ok(())
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Sets the execution time budget of a task, measured by `clock`.
+    /// A task exceeding it sees Context::should_yield() set.
+    ///
+    pub fn set_yield_budget(&mut self, clock: &'a dyn Clock, budget: u64) {
+        self.yield_clock = Some(clock);
+        self.yield_budget = budget;
+    }
+
+    /// Removes the execution time budget of tasks.
+    ///
+    pub fn clear_yield_budget(&mut self) {
+        self.yield_clock = None;
+    }
+
+    /// Measures the execution time of each task by `clock`, see
+    /// cpu_share().
+    ///
+    pub fn set_cpu_clock(&mut self, clock: &'a dyn Clock) {
+        self.cpu_clock = Some(clock);
+    }
+
+    /// Gets the share of task `id` in the execution time of all tasks
+    /// measured since set_cpu_clock() or reset_cpu_stats(), in per-mille.
+    /// The time of removed tasks stays in the total, so the shares don't
+    /// change by a removal. Only tasks of the fixed store are measured.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lwos::scheduler::{Error, Scheduler};
+    ///
+    /// let mut scheduler: Scheduler::<3> = Scheduler::new();
+    /// assert_eq!(scheduler.cpu_share(0), Err(Error::NoSuchTaskId));
+    /// ```
+    pub fn cpu_share(&self, id: TaskId) -> Result<u32, Error> {
+        self.get_ref(id)?;
+        let cpu_time = *self.cpu_times.get(id).ok_or(Error::InvalidParameter)?;
+
+        Ok(per_mille(cpu_time, self.cpu_total))
+    }
+
+    /// Clears the measured execution times, see cpu_share().
+    ///
+    pub fn reset_cpu_stats(&mut self) {
+        self.cpu_times = [0; SIZE];
+        self.cpu_total = 0;
+    }
+
+    /// Limits the number of directed yields (see Context::yield_to())
+    /// which may follow each other in a cycle. Further yields are ignored
+    /// and the normal order continues.
+    ///
+    pub fn set_max_yield_depth(&mut self, depth: usize) {
+        self.max_yield_depth = depth;
+    }
+
+    /// Gets the longest chain of directed yields seen so far.
+    ///
+    pub fn peak_yield_depth(&self) -> usize {
+        self.peak_yield_depth
+    }
+
+    /// Gets the scratch value of a task of the fixed store.
+    ///
+    pub fn scratch(&mut self, id: TaskId) -> Result<&mut S, Error> {
+        self.get(id)?;
+        self.scratch.get_mut(id).ok_or(Error::InvalidParameter)
+    }
+
+    /// Sends a message from task `from` to the inbox of task `to`. The
+    /// receiver gets it by Context::receive() in its next execution.
+    /// Only tasks in the fixed store have an inbox. Fails with
+    /// `Error::LimitExceeded` if the inbox is full.
+    ///
+    pub fn send(&mut self, from: TaskId, to: TaskId, msg: u32) -> Result<(), Error> {
+        self.get(from)?;
+        self.get(to)?;
+
+        Context::<S>::new(from, &mut self.inboxes).send(to, msg)
+    }
+
+    /// Subscribes task `id` to event `event` of the event bus, see
+    /// publish(). Only tasks in the fixed store can subscribe. Fails with
+    /// `Error::InvalidParameter` for event IDs of EVENT_COUNT and above.
+    ///
+    pub fn subscribe(&mut self, id: TaskId, event: u8) -> Result<(), Error> {
+        self.get(id)?;
+        let events = self
+            .subscriptions
+            .get_mut(id)
+            .ok_or(Error::InvalidParameter)?;
+
+        if EVENT_COUNT <= event {
+            return Err(Error::InvalidParameter);
+        }
+        *events |= 1 << event;
+        Ok(())
+    }
+
+    /// Unsubscribes task `id` from event `event`, see subscribe().
+    ///
+    pub fn unsubscribe(&mut self, id: TaskId, event: u8) -> Result<(), Error> {
+        self.get(id)?;
+        let events = self
+            .subscriptions
+            .get_mut(id)
+            .ok_or(Error::InvalidParameter)?;
+
+        if EVENT_COUNT <= event {
+            return Err(Error::InvalidParameter);
+        }
+        *events &= !(1 << event);
+        Ok(())
+    }
+
+    /// Publishes event `event` to all subscribed tasks. The payload is
+    /// queued into their inboxes, so they get it by Context::receive() in
+    /// their next execution, and waiting subscribers are resumed. Returns
+    /// the number of tasks the payload was queued for, subscribers with a
+    /// full inbox miss it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lwos::scheduler::Scheduler;
+    ///
+    /// let mut scheduler: Scheduler::<3> = Scheduler::new();
+    /// assert_eq!(scheduler.publish(1, 42), Ok(0));
+    /// ```
+    pub fn publish(&mut self, event: u8, payload: u32) -> Result<usize, Error> {
+        if EVENT_COUNT <= event {
+            return Err(Error::InvalidParameter);
+        }
+
+        let mut receivers = 0usize;
+        for id in 0..SIZE {
+            if 0 == self.subscriptions[id] & (1 << event) {
+                continue;
+            }
+
+            if let Some(task) = self.tasks[id].as_mut() {
+                if TaskState::Waiting == task.state {
+                    task.resume();
+                }
+                if self.inboxes[id].push(payload) {
+                    receivers += 1;
+                }
+            }
+        }
+
+        Ok(receivers)
+    }
+
+    /// Gets a handle of a task, which can only be used with this scheduler.
+    /// The handle of a task of the fixed store becomes stale when the task
+    /// is removed or moved by compact(), so it never resolves to another
+    /// task reusing the slot.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lwos::scheduler::{Error, Scheduler};
+    ///
+    /// let scheduler: Scheduler::<3> = Scheduler::new();
+    /// assert_eq!(scheduler.handle(0), Err(Error::NoSuchTaskId));
+    /// ```
+    pub fn handle(&self, id: TaskId) -> Result<Handle, Error> {
+        match self.slot(id) {
+            Some(Some(_)) => Ok(Handle {
+                id,
+                tag: self.tag,
+                generation: self.generation(id),
+            }),
+            Some(None) => Err(Error::NoSuchTaskId),
+            None => Err(Error::InvalidParameter),
+        }
+    }
+
+    /// Gets the task of a handle like get(). Fails with
+    /// `Error::WrongScheduler` if the handle belongs to another scheduler
+    /// and with `Error::NoSuchTaskId` if it is stale, see handle().
+    ///
+    pub fn get_by_handle(&mut self, handle: Handle) -> Result<&mut Task<'a, S>, Error> {
+        self.get(self.resolve(handle)?)
+    }
+
+    /// Removes the task of a handle like remove(). Fails with
+    /// `Error::WrongScheduler` if the handle belongs to another scheduler
+    /// and with `Error::NoSuchTaskId` if it is stale, see handle().
+    ///
+    pub fn remove_by_handle(&mut self, handle: Handle) -> Result<(), Error> {
+        self.remove(self.resolve(handle)?)
+    }
+
+    /// Gets the task ID of a handle of this scheduler.
+    ///
+    fn resolve(&self, handle: Handle) -> Result<TaskId, Error> {
+        if self.tag != handle.tag {
+            Err(Error::WrongScheduler)
+        } else if self.generation(handle.id) != handle.generation {
+            Err(Error::NoSuchTaskId)
+        } else {
+            Ok(handle.id)
+        }
+    }
+
+    /// Gets the generation of a slot, which changes whenever its task is
+    /// removed or moved. Slots of the overflow store have no generation.
+    ///
+    fn generation(&self, id: TaskId) -> u32 {
+        self.generations.get(id).copied().unwrap_or(0)
+    }
+
+    /// Invalidates the handles of a slot, see handle().
+    ///
+    fn bump_generation(&mut self, id: TaskId) {
+        if let Some(generation) = self.generations.get_mut(id) {
+            *generation = generation.wrapping_add(1);
+        }
+    }
+
+    pub fn get(&mut self, id: TaskId) -> Result<&mut Task<'a, S>, Error> {
+        match self.slot_mut(id) {
+            Some(Some(task)) => Ok(task),
+            Some(None) => Err(Error::NoSuchTaskId),
+            None => Err(Error::InvalidParameter),
+        }
+    }
+
+    /// Gets a task for reading, with the errors of get().
+    ///
+    fn get_ref(&self, id: TaskId) -> Result<&Task<'a, S>, Error> {
+        match self.slot(id) {
+            Some(Some(task)) => Ok(task),
+            Some(None) => Err(Error::NoSuchTaskId),
+            None => Err(Error::InvalidParameter),
+        }
+    }
+
+    /// Gets the executer of a task by its concrete type. Only tasks created
+    /// by Task::new_inspectable() support this, for others or a different
+    /// type InvalidParameter is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lwos::scheduler::Scheduler;
+    /// use lwos::task::{Task, TaskState, TaskId, Execute, TaskControl};
+    ///
+    /// struct Counter { runs: usize }
+    /// impl Execute for Counter {
+    ///     fn execute(&mut self, _id : TaskId) -> TaskControl {
+    ///         self.runs += 1;
+    ///         TaskControl::Continue
+    ///     }
+    /// }
+    ///
+    /// let mut scheduler: Scheduler::<3> = lwos::Scheduler::new();
+    /// let task = Task::new_inspectable(TaskState::Running, Box::new(Counter { runs: 0 }));
+    /// let id = scheduler.add(task).unwrap();
+    /// scheduler.process();
+    /// assert_eq!(scheduler.get_executer_mut::<Counter>(id).unwrap().runs, 1);
+    /// ```
+    #[cfg(feature = "alloc")]
+    pub fn get_executer_mut<T: Execute<S> + core::any::Any>(
+        &mut self,
+        id: TaskId,
+    ) -> Result<&mut T, Error> {
+        self.get(id)?
+            .func
+            .as_any_mut()
+            .and_then(|func| func.downcast_mut::<T>())
+            .ok_or(Error::InvalidParameter)
+    }
+
+    /// Requests task `id` to stop cooperatively, see Task::cancel(). Unlike
+    /// remove(), the task finishes its work and removes itself by
+    /// returning `TaskControl::Remove`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lwos::scheduler::{Error, Scheduler};
+    ///
+    /// let mut scheduler: Scheduler::<3> = Scheduler::new();
+    /// assert_eq!(scheduler.cancel(0), Err(Error::NoSuchTaskId));
+    /// ```
+    pub fn cancel(&mut self, id: TaskId) -> Result<(), Error> {
+        self.get(id)?.cancel();
+        Ok(())
+    }
+
+    /// Clears the last error codes of all tasks, see Task::last_error().
+    ///
+    pub fn clear_errors(&mut self) {
+        for index in 0..self.slot_count() {
+            if let Some(Some(task)) = self.slot_mut(index) {
+                task.clear_error();
+            }
+        }
+    }
+
+    /// Gets the number of tasks in the scheduler.
+    ///
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    /// Checks if the scheduler has no tasks.
+    ///
+    pub fn is_empty(&self) -> bool {
+        self.iter().next().is_none()
+    }
+
+    /// Iterates over all tasks and their IDs in ID order.
+    ///
+    pub fn iter(&self) -> impl Iterator<Item = (TaskId, &Task<'a, S>)> {
+        (0..self.slot_count()).filter_map(|id| match self.slot(id) {
+            Some(Some(task)) => Some((id, task)),
+            _ => None,
+        })
+    }
+
+    /// Writes a table of all tasks with ID, name, state, priority and run
+    /// count, one line per task after a header line, e.g. for a debug
+    /// console. Run counts of overflow tasks are not tracked and shown as 0.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lwos::scheduler::Scheduler;
+    ///
+    /// let scheduler: Scheduler::<3> = Scheduler::new();
+    /// let mut table = String::new();
+    /// scheduler.dump(&mut table).unwrap();
+    /// assert_eq!(table.lines().count(), 1);
+    /// ```
+    pub fn dump(&self, w: &mut impl core::fmt::Write) -> core::fmt::Result {
+        writeln!(
+            w,
+            "{:>3} {:<16} {:<9} {:>4} {:>10}",
+            "ID", "NAME", "STATE", "PRIO", "RUNS"
+        )?;
+
+        for (id, task) in self.iter() {
+            writeln!(
+                w,
+                "{:>3} {:<16} {:<9} {:>4} {:>10}",
+                id,
+                task.name().unwrap_or("-"),
+                task.state.name(),
+                task.priority,
+                self.runs.get(id).copied().unwrap_or(0)
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the links between the tasks as DOT graph, e.g. for rendering
+    /// the schedule with Graphviz. Each task is a node labeled with its ID,
+    /// name and phase. Edges point from a task to its successor set by
+    /// on_complete() and from a producer to its consumer set by
+    /// wait_for_runs().
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lwos::scheduler::Scheduler;
+    ///
+    /// let scheduler: Scheduler::<3> = Scheduler::new();
+    /// let mut graph = String::new();
+    /// scheduler.export_graph(&mut graph).unwrap();
+    /// assert_eq!(graph, "digraph tasks {\n}\n");
+    /// ```
+    pub fn export_graph(&self, w: &mut impl core::fmt::Write) -> core::fmt::Result {
+        writeln!(w, "digraph tasks {{")?;
+
+        for (id, task) in self.iter() {
+            writeln!(
+                w,
+                "  t{} [label=\"{} {}\\nphase {}\"];",
+                id,
+                id,
+                task.name().unwrap_or("-"),
+                task.phase
+            )?;
+        }
+
+        for (id, _) in self.iter() {
+            if let Some(next) = self.successors.get(id).filter(|next| INVALID_ID != **next) {
+                writeln!(w, "  t{} -> t{} [label=\"on_complete\"];", id, next)?;
+            }
+            if let Some(Some(gate)) = self.run_gates.get(id) {
+                writeln!(
+                    w,
+                    "  t{} -> t{} [label=\"runs {}\"];",
+                    gate.producer, id, gate.runs
+                )?;
+            }
+        }
+
+        writeln!(w, "}}")
+    }
+
+    /// Gets the number of task slots of both the fixed and overflow store.
+    ///
+    fn slot_count(&self) -> usize {
+        #[cfg(feature = "alloc")]
+        return SIZE + self.overflow.len();
+        #[cfg(not(feature = "alloc"))]
+        return SIZE;
+    }
+
+    /// Gets the slot of a task ID from the fixed or overflow store.
+    ///
+    fn slot(&self, id: TaskId) -> Option<&Option<Task<'a, S>>> {
+        if SIZE > id {
+            return Some(&self.tasks[id]);
+        }
+
+        #[cfg(feature = "alloc")]
+        return self.overflow.get(id - SIZE);
+        #[cfg(not(feature = "alloc"))]
+        return None;
+    }
+
+    /// Gets the mutable slot of a task ID from the fixed or overflow store.
+    ///
+    fn slot_mut(&mut self, id: TaskId) -> Option<&mut Option<Task<'a, S>>> {
+        if SIZE > id {
+            return Some(&mut self.tasks[id]);
+        }
+
+        #[cfg(feature = "alloc")]
+        return self.overflow.get_mut(id - SIZE);
+        #[cfg(not(feature = "alloc"))]
+        return None;
+    }
+
+    /// Gets the ID of the first task with the given name.
+    ///
+    pub fn find_by_name(&self, name: &str) -> Option<TaskId> {
+        self.iter()
+            .find(|(_, task)| Some(name) == task.name())
+            .map(|(id, _)| id)
+    }
+
+    /// Moves the tasks of the fixed store to its lowest slots, keeping
+    /// their relative order, and returns the new count of tasks packed
+    /// this way, including those already in place. Tasks of reserved IDs
+    /// and of the overflow store keep their slots and are not counted.
+    /// Note that this changes the ID of moved tasks, IDs obtained before
+    /// calling this are no longer valid. The cursor of
+    /// process_budget() follows the moved tasks, so the next budgeted call
+    /// continues with the same task.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lwos::scheduler::Scheduler;
+    ///
+    /// let mut scheduler: Scheduler::<3> = Scheduler::new();
+    /// assert_eq!(scheduler.compact(), 0);
+    /// ```
+    pub fn compact(&mut self) -> usize {
+        let mut count = 0usize;
+        let mut target = 0usize;
+        let mut cursor = None;
+
+        for index in 0..SIZE {
+            if self.reserved[index] || self.tasks[index].is_none() {
+                continue;
+            }
+
+            while self.reserved[target] {
+                target += 1;
+            }
+            if (index >= self.budget_cursor) && cursor.is_none() {
+                cursor = Some(target);
+            }
+            if index != target {
+                self.tasks.swap(index, target);
+                self.successors.swap(index, target);
+                for successor in self.successors.iter_mut() {
+                    if index == *successor {
+                        *successor = target;
+                    }
+                }
+                self.min_rates.swap(index, target);
+                self.last_runs.swap(index, target);
+                self.runs.swap(index, target);
+                self.run_gates.swap(index, target);
+                for gate in self.run_gates.iter_mut().flatten() {
+                    if index == gate.producer {
+                        gate.producer = target;
+                    }
+                }
+                self.inboxes.swap(index, target);
+                self.subscriptions.swap(index, target);
+                self.ceilings.swap(index, target);
+                self.deferred_actions.swap(index, target);
+                self.cpu_times.swap(index, target);
+                self.added.swap(index, target);
+                self.bump_generation(index);
+                self.bump_generation(target);
+                self.scratch.swap(index, target);
+            }
+            target += 1;
+            count += 1;
+        }
+        self.frozen = None;
+        if SIZE > self.budget_cursor {
+            self.budget_cursor = cursor.unwrap_or(target);
+        }
+
+        count
+    }
+
+    /// Saves the states of all tasks into `out`. Empty slots are stored as
+    /// `EMPTY_SLOT_STATE`.
+    ///
+    pub fn save_states(&self, out: &mut [u8; SIZE]) {
+        for (byte, item) in out.iter_mut().zip(self.tasks.iter()) {
+            *byte = match item {
+                Some(task) => task.state.as_u8(),
+                None => EMPTY_SLOT_STATE,
+            };
+        }
+    }
+
+    /// Restores task states saved by save_states(). Empty slots and slots
+    /// saved as `EMPTY_SLOT_STATE` are skipped. If any other byte for an
+    /// occupied slot is not a valid state, nothing is restored and
+    /// `Error::InvalidParameter` is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lwos::scheduler::Scheduler;
+    ///
+    /// let mut scheduler: Scheduler::<3> = Scheduler::new();
+    /// let mut saved = [0u8; 3];
+    /// scheduler.save_states(&mut saved);
+    /// assert_eq!(scheduler.restore_states(&saved), Ok(()));
+    /// ```
+    pub fn restore_states(&mut self, saved: &[u8; SIZE]) -> Result<(), Error> {
+        for (byte, item) in saved.iter().zip(self.tasks.iter()) {
+            if item.is_some()
+                && (EMPTY_SLOT_STATE != *byte)
+                && TaskState::try_from_u8(*byte).is_none()
+            {
+                return Err(Error::InvalidParameter);
+            }
+        }
+
+        for (byte, item) in saved.iter().zip(self.tasks.iter_mut()) {
+            if let (Some(task), Some(state)) = (item, TaskState::try_from_u8(*byte)) {
+                task.state = state;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Sets the number of cycles a task may wait for its signal before it
+    /// is reported as deadlocked. `None` disables the detection.
+    ///
+    pub fn set_deadlock_threshold(&mut self, cycles: Option<usize>) {
+        self.deadlock_threshold = cycles;
+    }
+
+    /// Gets the IDs of all tasks waiting for a signal longer than the
+    /// deadlock threshold.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lwos::scheduler::Scheduler;
+    ///
+    /// let mut scheduler: Scheduler::<3> = Scheduler::new();
+    /// scheduler.set_deadlock_threshold(Some(10));
+    /// assert_eq!(scheduler.deadlocked_ids().count(), 0);
+    /// ```
+    pub fn deadlocked_ids(&self) -> impl Iterator<Item = TaskId> + use<'_, 'a, SIZE, S> {
+        let threshold = self.deadlock_threshold;
+
+        self.iter().filter_map(move |(id, task)| match threshold {
+            Some(limit) if task.waiting_cycles() > limit => Some(id),
+            _ => None,
+        })
+    }
+
+    /// Gets the instrumentation counters (requires the `metrics` feature).
+    ///
+    #[cfg(feature = "metrics")]
+    pub fn metrics(&self) -> &Metrics {
+        &self.metrics
+    }
+
+    /// Gets the maximum number of tasks supported by this scheduler.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lwos::scheduler::Scheduler;
+    ///
+    /// let scheduler: Scheduler::<3> = Scheduler::new();
+    /// assert_eq!(scheduler.capacity(), 3);
+    /// ```
+    pub const fn capacity(&self) -> usize {
+        SIZE
+    }
+}
+
+#[cfg(feature = "metrics")]
+impl Metrics {
+    /// Accounts a process cycle.
+    ///
+    fn record_cycle(&mut self, scans: usize, executions: usize) {
+        self.process_calls = self.process_calls.saturating_add(1);
+        self.slot_scans = self.slot_scans.saturating_add(scans);
+        self.executions = self.executions.saturating_add(executions);
+        self.max_executions = self.max_executions.max(executions);
+    }
+}
+
+impl Handle {
+    /// Gets the task ID of the handle.
+    ///
+    pub fn id(&self) -> TaskId {
+        self.id
+    }
+}
+
+/// Gets `part` of `total` in per-mille, zero for a total of zero.
+///
+fn per_mille(part: u64, total: u64) -> u32 {
+    if 0 == total {
+        return 0;
+    }
+
+    (part as u128 * 1000 / total as u128) as u32
+}
+
+/// Advances the state of the linear congruential generator used for
+/// pseudo random scheduling decisions.
+///
+fn lcg_next(state: u64) -> u64 {
+    state
+        .wrapping_mul(6_364_136_223_846_793_005)
+        .wrapping_add(1_442_695_040_888_963_407)
+}
+
+/// Gets the pseudo random number of a generator state. Only the upper
+/// half is used, as the low bits of a power of two LCG repeat with a short
+/// period.
+///
+fn lcg_output(state: u64) -> u32 {
+    (state >> 32) as u32
+}
+
+impl<'a, const N: usize, S: Default> SubSchedulerExecuter<'a, N, S> {
+    /// Wraps a scheduler for running it as task of another scheduler.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lwos::scheduler::{Scheduler, SubSchedulerExecuter};
+    /// use lwos::task::{Task, TaskState};
+    ///
+    /// let mut child = SubSchedulerExecuter::new(Scheduler::<2>::new());
+    /// let mut parent: Scheduler::<2> = Scheduler::new();
+    /// parent.add(Task::new(TaskState::Running, &mut child)).unwrap();
+    /// parent.process();
+    /// ```
+    pub fn new(scheduler: Scheduler<'a, N, S>) -> Self {
+        SubSchedulerExecuter { scheduler }
+    }
+
+    /// Gets the inner scheduler.
+    ///
+    pub fn scheduler(&self) -> &Scheduler<'a, N, S> {
+        &self.scheduler
+    }
+
+    /// Gets the inner scheduler for modification, e.g. to add tasks.
+    ///
+    pub fn scheduler_mut(&mut self) -> &mut Scheduler<'a, N, S> {
+        &mut self.scheduler
+    }
+}
+
+impl<const N: usize, S: Default, P> Execute<P> for SubSchedulerExecuter<'_, N, S> {
+    fn execute(&mut self, _id: TaskId) -> TaskControl {
+        self.scheduler.process();
+        TaskControl::Continue
+    }
+}
+
+impl CriticalSection for NoCriticalSection {
+    fn with<R>(&self, f: impl FnOnce() -> R) -> R {
+        f()
+    }
+}
+
+impl Inbox {
+    /// Appends a message, returns false if the inbox is full.
+    ///
+    fn push(&mut self, msg: u32) -> bool {
+        if INBOX_SIZE <= self.len {
+            return false;
+        }
+
+        self.messages[(self.head + self.len) % INBOX_SIZE] = msg;
+        self.len += 1;
+        true
+    }
+
+    /// Removes the oldest message.
+    ///
+    fn pop(&mut self) -> Option<u32> {
+        if 0 == self.len {
+            return None;
+        }
+
+        let msg = self.messages[self.head];
+        self.head = (self.head + 1) % INBOX_SIZE;
+        self.len -= 1;
+        Some(msg)
+    }
+}
+
+impl Deferred {
+    /// Appends the ID of a deferring task, returns false if the queue is
+    /// full.
+    ///
+    fn push(&mut self, id: TaskId) -> bool {
+        match self.work.get_mut(self.len) {
+            Some(slot) => {
+                *slot = id;
+                self.len += 1;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl<'c, S> Context<'c, S> {
+    /// Creates the context for executing task `id`.
+    ///
+    fn new(id: TaskId, inboxes: &'c mut [Inbox]) -> Self {
+        Context {
+            id,
+            inboxes,
+            clock: None,
+            started: 0,
+            budget: 0,
+            yield_target: None,
+            scratch: None,
+            view: None,
+            deferred: None,
+            cancelled: false,
+        }
+    }
+
+    /// Starts measuring the execution time for should_yield().
+    ///
+    fn start_budget(&mut self, clock: &'c dyn Clock, budget: u64) {
+        self.started = clock.now();
+        self.budget = budget;
+        self.clock = Some(clock);
+    }
+
+    /// Creates a context without any inboxes, e.g. for executing a task
+    /// outside of a scheduler.
+    ///
+    pub fn detached(id: TaskId) -> Self {
+        Self::new(id, &mut [])
+    }
+
+    /// Gets the ID of the executed task.
+    ///
+    pub fn id(&self) -> TaskId {
+        self.id
+    }
+
+    /// Checks if the task used up its execution time budget (see
+    /// Scheduler::set_yield_budget()). A long running task should poll this
+    /// between chunks of work and return `TaskControl::Yield` if set.
+    ///
+    pub fn should_yield(&self) -> bool {
+        self.clock
+            .is_some_and(|clock| clock.now().wrapping_sub(self.started) >= self.budget)
+    }
+
+    /// Requests task `target` to execute right after this task in the
+    /// current process() cycle, before the normal order continues. Only
+    /// tasks of the fixed store are supported, and each task executes at
+    /// most once per cycle. Other process variants ignore the request.
+    ///
+    pub fn yield_to(&mut self, target: TaskId) {
+        self.yield_target = Some(target);
+    }
+
+    /// Gets the scratch value of the executed task, see
+    /// Scheduler::scratch(). Tasks of the overflow store and detached
+    /// contexts have no scratch value.
+    ///
+    pub fn scratch(&mut self) -> Option<&mut S> {
+        self.scratch.as_deref_mut()
+    }
+
+    /// Gets a read-only view of the other tasks of the scheduler, e.g. for
+    /// a supervisor task. Detached contexts have no view.
+    ///
+    pub fn view(&self) -> Option<&SchedulerView<'c>> {
+        self.view.as_ref()
+    }
+
+    /// Defers the action of the executed task (see
+    /// Scheduler::set_deferred_action()) to the end of the current process
+    /// cycle, after all tasks executed, e.g. for cleanup. The action runs
+    /// once per call, a task without action defers nothing. Fails with
+    /// `Error::LimitExceeded` if DEFER_SIZE actions are queued already and
+    /// with `Error::InvalidParameter` for a detached context.
+    ///
+    pub fn defer(&mut self) -> Result<(), Error> {
+        match self
+            .deferred
+            .as_mut()
+            .map(|deferred| deferred.push(self.id))
+        {
+            Some(true) => Ok(()),
+            Some(false) => Err(Error::LimitExceeded),
+            None => Err(Error::InvalidParameter),
+        }
+    }
+
+    /// Checks if the executed task was requested to stop, see
+    /// Scheduler::cancel().
+    ///
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled
+    }
+
+    /// Takes the oldest message from the inbox of the executed task.
+    ///
+    pub fn receive(&mut self) -> Option<u32> {
+        self.inboxes.get_mut(self.id).and_then(Inbox::pop)
+    }
+
+    /// Sends a message to the inbox of task `to`. Fails with
+    /// `Error::InvalidParameter` if the task has no inbox and with
+    /// `Error::LimitExceeded` if the inbox is full.
+    ///
+    pub fn send(&mut self, to: TaskId, msg: u32) -> Result<(), Error> {
+        match self.inboxes.get_mut(to).map(|inbox| inbox.push(msg)) {
+            Some(true) => Ok(()),
+            Some(false) => Err(Error::LimitExceeded),
+            None => Err(Error::InvalidParameter),
+        }
+    }
+}
+
+impl SchedulerView<'_> {
+    /// Gets the state of a task. The executed task itself is always
+    /// running.
+    ///
+    pub fn state_of(&self, id: TaskId) -> Option<TaskState> {
+        if self.id == id {
+            return Some(TaskState::Running);
+        }
+        self.slots.state_of(id)
+    }
+
+    /// Gets the name of a task, see Task::named(). The name of the executed
+    /// task itself is not available.
+    ///
+    pub fn name_of(&self, id: TaskId) -> Option<&'static str> {
+        self.slots.name_of(id)
+    }
+
+    /// Gets the CPU share of a task like Scheduler::cpu_share(), as of the
+    /// start of the executed task.
+    ///
+    pub fn cpu_share_of(&self, id: TaskId) -> Option<u32> {
+        self.state_of(id)?;
+        self.slots.cpu_share_of(id)
+    }
+
+    /// Gets the number of tasks in the scheduler, including the executed
+    /// one.
+    ///
+    pub fn len(&self) -> usize {
+        self.slots.count() + 1
+    }
+
+    /// Checks if the scheduler has no tasks, which is never the case while
+    /// a task executes.
+    ///
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+}
+
+impl<'a, S> Slots<'_, 'a, S> {
+    /// Gets the task of a slot from the fixed or overflow store.
+    ///
+    fn task(&self, id: TaskId) -> Option<&Task<'a, S>> {
+        match id.checked_sub(self.fixed.len()) {
+            None => self.fixed[id].as_ref(),
+            Some(index) => self.overflow.get(index)?.as_ref(),
+        }
+    }
+}
+
+impl<S> TaskSlots for Slots<'_, '_, S> {
+    fn state_of(&self, id: TaskId) -> Option<TaskState> {
+        self.task(id).map(|t| t.state)
+    }
+
+    fn name_of(&self, id: TaskId) -> Option<&'static str> {
+        self.task(id).and_then(Task::name)
+    }
+
+    fn cpu_share_of(&self, id: TaskId) -> Option<u32> {
+        let cpu_time = *self.cpu_times.get(id)?;
+        Some(per_mille(cpu_time, self.cpu_total))
+    }
+
+    fn count(&self) -> usize {
+        self.fixed.iter().chain(self.overflow).flatten().count()
+    }
+}
+
+impl<'a, const SIZE: usize, S: Default> Default for Scheduler<'a, SIZE, S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "alloc")]
+    use alloc::boxed::Box;
+    use core::cell::{Cell, RefCell};
+
+    struct SomeExecuter {}
+    impl<S> Execute<S> for SomeExecuter {
+        fn execute(&mut self, _id: TaskId) -> TaskControl {
+            TaskControl::Continue
+        }
+    }
+
+    /// Records the IDs of executed tasks in execution order.
+    #[derive(Default)]
+    struct Log {
+        ids: [TaskId; 32],
+        len: usize,
+    }
+    impl Log {
+        fn entries(&self) -> &[TaskId] {
+            &self.ids[..self.len]
+        }
+
+        fn push(&mut self, id: TaskId) {
+            self.ids[self.len] = id;
+            self.len += 1;
+        }
+    }
+
+    struct LogExecuter<'a> {
+        log: &'a RefCell<Log>,
+    }
+    impl<S> Execute<S> for LogExecuter<'_> {
+        fn execute(&mut self, id: TaskId) -> TaskControl {
+            self.log.borrow_mut().push(id);
+            TaskControl::Continue
+        }
+    }
+
+    #[test]
+    fn scheduler_add() {
+        let mut scheduler: Scheduler<1> = Scheduler::new();
+        assert!(scheduler.tasks[0].is_none());
+
+        let mut e1: SomeExecuter = SomeExecuter {};
+        let t1 = Task::new(TaskState::Running, &mut e1);
+
+        assert_eq!(scheduler.add(t1).unwrap(), 0);
+
+        assert_eq!(
+            scheduler.tasks[0].as_ref().unwrap().state,
+            TaskState::Running
+        );
+    }
+
+    #[test]
+    fn scheduler_add_capacity() {
+        let mut scheduler: Scheduler<3> = Scheduler::new();
+
+        let mut e1: SomeExecuter = SomeExecuter {};
+        let mut e2: SomeExecuter = SomeExecuter {};
+        let mut e3: SomeExecuter = SomeExecuter {};
+        let mut e4: SomeExecuter = SomeExecuter {};
+
+        let t1 = Task::new(TaskState::Running, &mut e1);
+        let t2 = Task::new(TaskState::Running, &mut e2);
+        let t3 = Task::new(TaskState::Running, &mut e3);
+        let t4 = Task::new(TaskState::Running, &mut e4);
+
+        assert_eq!(scheduler.add(t1).unwrap(), 0);
+        assert_eq!(scheduler.add(t2).unwrap(), 1);
+        assert_eq!(scheduler.add(t3).unwrap(), 2);
+        assert_eq!(scheduler.add(t4).unwrap_err(), Error::LimitExceeded);
+    }
+
+    #[test]
+    #[should_panic]
+    fn scheduler_add_too_much() {
+        let mut scheduler: Scheduler<1> = Scheduler::new();
+        let mut e1: SomeExecuter = SomeExecuter {};
+        let mut e2: SomeExecuter = SomeExecuter {};
+        let t1 = Task::new(TaskState::Running, &mut e1);
+        let t2 = Task::new(TaskState::Running, &mut e2);
+
+        assert_eq!(scheduler.add(t1).unwrap(), 0);
+        assert_eq!(scheduler.add(t2).unwrap(), 1); // <- panics (capacity)
+    }
+
+    #[test]
+    fn scheduler_remove() {
+        let mut scheduler: Scheduler<1> = Scheduler::new();
+        let mut e1: SomeExecuter = SomeExecuter {};
+        let t1 = Task::new(TaskState::Running, &mut e1);
+
+        assert_eq!(scheduler.add(t1).unwrap(), 0);
+        assert_eq!(scheduler.remove(0), Ok(()));
         assert_eq!(scheduler.remove(0).unwrap_err(), Error::NoSuchTaskId);
         assert_eq!(scheduler.remove(1).unwrap_err(), Error::InvalidParameter);
     }
+
+    struct IdExecuter<'a> {
+        id: &'a core::cell::Cell<TaskId>,
+    }
+    impl Execute for IdExecuter<'_> {
+        fn execute(&mut self, id: TaskId) -> TaskControl {
+            self.id.set(id);
+            TaskControl::Continue
+        }
+    }
+
+    #[test]
+    fn scheduler_process_mutating_executer() {
+        struct CountExecuter {
+            count: usize,
+        }
+        impl Execute for CountExecuter {
+            fn execute(&mut self, _id: TaskId) -> TaskControl {
+                self.count += 1;
+                TaskControl::Continue
+            }
+        }
+
+        let mut counter = CountExecuter { count: 0 };
+        {
+            let mut scheduler: Scheduler<1> = Scheduler::new();
+            scheduler
+                .add(Task::new(TaskState::Running, &mut counter))
+                .unwrap();
+
+            scheduler.process();
+            scheduler.process();
+            scheduler.process();
+        }
+        assert_eq!(counter.count, 3);
+    }
+
+    #[test]
+    fn scheduler_compact() {
+        let ids = [
+            core::cell::Cell::new(INVALID_ID),
+            core::cell::Cell::new(INVALID_ID),
+            core::cell::Cell::new(INVALID_ID),
+            core::cell::Cell::new(INVALID_ID),
+        ];
+        let mut e0 = IdExecuter { id: &ids[0] };
+        let mut e1 = IdExecuter { id: &ids[1] };
+        let mut e2 = IdExecuter { id: &ids[2] };
+        let mut e3 = IdExecuter { id: &ids[3] };
+        let mut e4 = IdExecuter { id: &ids[0] };
+        let mut e5 = IdExecuter { id: &ids[2] };
+        let mut scheduler: Scheduler<5> = Scheduler::new();
+
+        scheduler
+            .add(Task::new(TaskState::Running, &mut e0))
+            .unwrap();
+        scheduler
+            .add(Task::new(TaskState::Running, &mut e1))
+            .unwrap();
+        scheduler
+            .add(Task::new(TaskState::Suspended, &mut e2))
+            .unwrap();
+        scheduler
+            .add(Task::new(TaskState::Running, &mut e3))
+            .unwrap();
+        assert_eq!(scheduler.remove(0), Ok(()));
+        assert_eq!(scheduler.remove(2), Ok(()));
+
+        assert_eq!(scheduler.compact(), 2);
+        assert!(scheduler.tasks[0].is_some());
+        assert!(scheduler.tasks[1].is_some());
+        assert!(scheduler.tasks[2..].iter().all(|t| t.is_none()));
+
+        scheduler.process();
+        assert_eq!(ids[1].get(), 0);
+        assert_eq!(ids[3].get(), 1);
+
+        // tasks already in place count as well
+        assert_eq!(scheduler.compact(), 2);
+        scheduler
+            .add(Task::new(TaskState::Running, &mut e4))
+            .unwrap();
+        scheduler
+            .add(Task::new(TaskState::Running, &mut e5))
+            .unwrap();
+        assert_eq!(scheduler.remove(2), Ok(()));
+        assert_eq!(scheduler.compact(), 3);
+        assert!(scheduler.tasks[3..].iter().all(|t| t.is_none()));
+
+        scheduler.process();
+        assert_eq!(ids[1].get(), 0);
+        assert_eq!(ids[3].get(), 1);
+        assert_eq!(ids[2].get(), 2);
+        assert_eq!(ids[0].get(), INVALID_ID);
+    }
+
+    #[test]
+    fn scheduler_process_mask() {
+        let mut e0: SomeExecuter = SomeExecuter {};
+        let mut e1: SomeExecuter = SomeExecuter {};
+        let mut e2: SomeExecuter = SomeExecuter {};
+        let mut scheduler: Scheduler<4> = Scheduler::new();
+
+        scheduler
+            .add(Task::new(TaskState::Running, &mut e0))
+            .unwrap();
+        scheduler
+            .add(Task::new(TaskState::Suspended, &mut e1))
+            .unwrap();
+        scheduler
+            .add(Task::new(TaskState::Running, &mut e2))
+            .unwrap();
+
+        assert_eq!(scheduler.process_mask(), 0b0101);
+
+        scheduler.get(1).unwrap().resume();
+        scheduler.get(2).unwrap().suspend();
+        assert_eq!(scheduler.process_mask(), 0b0011);
+    }
+
+    #[test]
+    fn scheduler_add_delayed() {
+        let mut e0: SomeExecuter = SomeExecuter {};
+        let mut scheduler: Scheduler<1> = Scheduler::new();
+
+        let id = scheduler
+            .add_delayed(Task::new(TaskState::Running, &mut e0), 3)
+            .unwrap();
+
+        for _ in 0..3 {
+            assert_eq!(scheduler.process_mask(), 0);
+            assert_eq!(scheduler.get(id).unwrap().state, TaskState::Delayed);
+        }
+        assert_eq!(scheduler.process_mask(), 1);
+        assert_eq!(scheduler.get(id).unwrap().state, TaskState::Running);
+    }
+
+    #[test]
+    fn scheduler_save_restore_states() {
+        let mut e0: SomeExecuter = SomeExecuter {};
+        let mut e1: SomeExecuter = SomeExecuter {};
+        let mut e2: SomeExecuter = SomeExecuter {};
+        let mut scheduler: Scheduler<4> = Scheduler::new();
+
+        scheduler
+            .add(Task::new(TaskState::Running, &mut e0))
+            .unwrap();
+        scheduler
+            .add(Task::new(TaskState::Suspended, &mut e1))
+            .unwrap();
+        scheduler
+            .add(Task::new(TaskState::Running, &mut e2))
+            .unwrap();
+        assert_eq!(scheduler.remove(1), Ok(()));
+
+        let mut saved = [0u8; 4];
+        scheduler.save_states(&mut saved);
+        assert_eq!(
+            saved,
+            [
+                TaskState::Running.as_u8(),
+                EMPTY_SLOT_STATE,
+                TaskState::Running.as_u8(),
+                EMPTY_SLOT_STATE
+            ]
+        );
+
+        scheduler.get(0).unwrap().suspend();
+        scheduler.get(2).unwrap().suspend();
+        let mut e3: SomeExecuter = SomeExecuter {};
+        assert_eq!(
+            scheduler
+                .add(Task::new(TaskState::Suspended, &mut e3))
+                .unwrap(),
+            1
+        );
+
+        let mut invalid = saved;
+        invalid[0] = 42;
+        assert_eq!(
+            scheduler.restore_states(&invalid),
+            Err(Error::InvalidParameter)
+        );
+        assert_eq!(scheduler.get(0).unwrap().state, TaskState::Suspended);
+
+        assert_eq!(scheduler.restore_states(&saved), Ok(()));
+        assert_eq!(scheduler.get(0).unwrap().state, TaskState::Running);
+        assert_eq!(scheduler.get(1).unwrap().state, TaskState::Suspended);
+        assert_eq!(scheduler.get(2).unwrap().state, TaskState::Running);
+    }
+
+    #[test]
+    fn scheduler_fairness_seed() {
+        let log = RefCell::new(Log::default());
+        let mut e0 = LogExecuter { log: &log };
+        let mut e1 = LogExecuter { log: &log };
+        let mut e2 = LogExecuter { log: &log };
+        let mut scheduler: Scheduler<3> = Scheduler::new();
+
+        scheduler
+            .add(Task::new(TaskState::Running, &mut e0))
+            .unwrap();
+        scheduler
+            .add(Task::new(TaskState::Running, &mut e1))
+            .unwrap();
+        scheduler
+            .add(Task::new(TaskState::Running, &mut e2))
+            .unwrap();
+
+        scheduler.process();
+        assert_eq!(log.borrow().entries(), [0, 1, 2]);
+
+        let mut reference: Scheduler<3> = Scheduler::new();
+        reference.set_fairness_seed(1234);
+        scheduler.set_fairness_seed(1234);
+
+        let mut starts = [0usize; 8];
+        for start in starts.iter_mut() {
+            *start = reference.next_start();
+        }
+        assert!(starts.iter().any(|s| 0 != *s));
+
+        for start in starts {
+            log.borrow_mut().len = 0;
+            scheduler.process();
+            assert_eq!(
+                log.borrow().entries(),
+                [start, (start + 1) % 3, (start + 2) % 3]
+            );
+        }
+    }
+
+    /// Completes after a given number of executions.
+    struct FiniteExecuter {
+        runs: usize,
+    }
+    impl Execute for FiniteExecuter {
+        fn execute(&mut self, _id: TaskId) -> TaskControl {
+            self.runs -= 1;
+
+            if 0 == self.runs {
+                TaskControl::Remove
+            } else {
+                TaskControl::Continue
+            }
+        }
+    }
+
+    #[test]
+    fn scheduler_on_complete() {
+        let mut a = FiniteExecuter { runs: 2 };
+        let mut b: SomeExecuter = SomeExecuter {};
+        let mut scheduler: Scheduler<2> = Scheduler::new();
+
+        let id_a = scheduler
+            .add(Task::new(TaskState::Running, &mut a))
+            .unwrap();
+        let id_b = scheduler
+            .add(Task::new(TaskState::Waiting, &mut b))
+            .unwrap();
+        assert_eq!(scheduler.on_complete(id_a, 5), Err(Error::InvalidParameter));
+        assert_eq!(scheduler.on_complete(id_a, id_b), Ok(()));
+
+        assert_eq!(scheduler.process_mask(), 0b01);
+        assert_eq!(scheduler.process_mask(), 0b11);
+        assert_eq!(scheduler.remove(id_a), Err(Error::NoSuchTaskId));
+        assert_eq!(scheduler.process_mask(), 0b10);
+    }
+
+    #[test]
+    fn scheduler_on_complete_compact() {
+        let mut filler: SomeExecuter = SomeExecuter {};
+        let mut a = FiniteExecuter { runs: 1 };
+        let mut b: SomeExecuter = SomeExecuter {};
+        let mut scheduler: Scheduler<3> = Scheduler::new();
+
+        scheduler
+            .add(Task::new(TaskState::Running, &mut filler))
+            .unwrap();
+        scheduler
+            .add(Task::new(TaskState::Running, &mut a))
+            .unwrap();
+        scheduler
+            .add(Task::new(TaskState::Suspended, &mut b))
+            .unwrap();
+        assert_eq!(scheduler.on_complete(1, 2), Ok(()));
+        assert_eq!(scheduler.remove(0), Ok(()));
+        assert_eq!(scheduler.compact(), 2);
+
+        assert_eq!(scheduler.process_mask(), 0b11);
+        assert_eq!(scheduler.get(0).err(), Some(Error::NoSuchTaskId));
+        assert_eq!(scheduler.get(1).unwrap().state, TaskState::Running);
+    }
+
+    #[test]
+    fn scheduler_remove_links() {
+        let mut a = FiniteExecuter { runs: 1 };
+        let mut b: SomeExecuter = SomeExecuter {};
+        let mut n: SomeExecuter = SomeExecuter {};
+        let mut c: SomeExecuter = SomeExecuter {};
+        let mut scheduler: Scheduler<3> = Scheduler::new();
+
+        scheduler
+            .add(Task::new(TaskState::Running, &mut a))
+            .unwrap();
+        scheduler
+            .add(Task::new(TaskState::Suspended, &mut b))
+            .unwrap();
+        scheduler
+            .add(Task::new(TaskState::Running, &mut c))
+            .unwrap();
+        assert_eq!(scheduler.on_complete(0, 1), Ok(()));
+        assert_eq!(scheduler.wait_for_runs(2, 1, 1), Ok(()));
+
+        assert_eq!(
+            scheduler.remove_by_handle(scheduler.handle(1).unwrap()),
+            Ok(())
+        );
+        scheduler
+            .add(Task::new(TaskState::Suspended, &mut n))
+            .unwrap();
+
+        assert_eq!(scheduler.process_mask(), 0b101);
+        assert_eq!(scheduler.get(1).unwrap().state, TaskState::Suspended);
+    }
+
+    #[test]
+    fn scheduler_find_by_name() {
+        let mut e0: SomeExecuter = SomeExecuter {};
+        let mut e1: SomeExecuter = SomeExecuter {};
+        let mut e2: SomeExecuter = SomeExecuter {};
+        let mut e3: SomeExecuter = SomeExecuter {};
+        let mut scheduler: Scheduler<4> = Scheduler::new();
+
+        scheduler
+            .add(Task::new(TaskState::Running, &mut e0))
+            .unwrap();
+        scheduler
+            .add(Task::named(TaskState::Running, "input", &mut e1))
+            .unwrap();
+        scheduler
+            .add(Task::named(TaskState::Running, "output", &mut e2))
+            .unwrap();
+        scheduler
+            .add(Task::named(TaskState::Running, "input", &mut e3))
+            .unwrap();
+
+        assert_eq!(scheduler.find_by_name("input"), Some(1));
+        assert_eq!(scheduler.find_by_name("output"), Some(2));
+        assert_eq!(scheduler.find_by_name("missing"), None);
+    }
+
+    #[test]
+    fn scheduler_priority_aging() {
+        let mut high: SomeExecuter = SomeExecuter {};
+        let mut low: SomeExecuter = SomeExecuter {};
+        let mut scheduler: Scheduler<2> = Scheduler::new();
+
+        let mut t = Task::new(TaskState::Running, &mut high);
+        t.priority = 10;
+        let id_high = scheduler.add(t).unwrap();
+        let id_low = scheduler
+            .add(Task::new(TaskState::Running, &mut low))
+            .unwrap();
+
+        for _ in 0..20 {
+            assert_eq!(scheduler.process_priority(), Some(id_high));
+        }
+
+        scheduler.enable_aging(2);
+        for _ in 0..21 {
+            assert_eq!(scheduler.process_priority(), Some(id_high));
+        }
+        assert_eq!(scheduler.get(id_low).unwrap().effective_priority(), 10);
+        assert_eq!(scheduler.process_priority(), Some(id_high));
+        assert_eq!(scheduler.process_priority(), Some(id_low));
+        assert_eq!(scheduler.get(id_low).unwrap().effective_priority(), 0);
+        assert_eq!(scheduler.process_priority(), Some(id_high));
+    }
+
+    #[cfg(feature = "metrics")]
+    #[test]
+    fn scheduler_metrics() {
+        let mut e0: SomeExecuter = SomeExecuter {};
+        let mut e1: SomeExecuter = SomeExecuter {};
+        let mut scheduler: Scheduler<4> = Scheduler::new();
+
+        scheduler
+            .add(Task::new(TaskState::Running, &mut e0))
+            .unwrap();
+        scheduler
+            .add(Task::new(TaskState::Running, &mut e1))
+            .unwrap();
+
+        scheduler.process();
+        scheduler.get(1).unwrap().suspend();
+        scheduler.process();
+        scheduler.process_priority();
+
+        assert_eq!(
+            *scheduler.metrics(),
+            Metrics {
+                process_calls: 3,
+                slot_scans: 12,
+                executions: 4,
+                max_executions: 2,
+            }
+        );
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn scheduler_get_executer_mut() {
+        let mut borrowed = SomeExecuter {};
+        let mut scheduler: Scheduler<3> = Scheduler::new();
+        let id = scheduler
+            .add(Task::new_inspectable(
+                TaskState::Running,
+                Box::new(FiniteExecuter { runs: 3 }),
+            ))
+            .unwrap();
+        let id_borrowed = scheduler
+            .add(Task::new(TaskState::Running, &mut borrowed))
+            .unwrap();
+
+        scheduler.process();
+        assert_eq!(
+            scheduler
+                .get_executer_mut::<FiniteExecuter>(id)
+                .unwrap()
+                .runs,
+            2
+        );
+        assert_eq!(
+            scheduler.get_executer_mut::<SomeExecuter>(id).err(),
+            Some(Error::InvalidParameter)
+        );
+        assert_eq!(
+            scheduler
+                .get_executer_mut::<SomeExecuter>(id_borrowed)
+                .err(),
+            Some(Error::InvalidParameter)
+        );
+        assert_eq!(
+            scheduler.get_executer_mut::<FiniteExecuter>(2).err(),
+            Some(Error::NoSuchTaskId)
+        );
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn scheduler_overflow() {
+        let log = RefCell::new(Log::default());
+        let mut e0 = LogExecuter { log: &log };
+        let mut e1 = LogExecuter { log: &log };
+        let mut e2 = LogExecuter { log: &log };
+        let mut e3 = LogExecuter { log: &log };
+        let mut scheduler: Scheduler<2> = Scheduler::new();
+        scheduler.set_overflow_limit(1);
+
+        assert_eq!(scheduler.add(Task::new(TaskState::Running, &mut e0)), Ok(0));
+        assert_eq!(scheduler.add(Task::new(TaskState::Running, &mut e1)), Ok(1));
+        assert_eq!(scheduler.add(Task::new(TaskState::Running, &mut e2)), Ok(2));
+        assert_eq!(
+            scheduler
+                .add(Task::new(TaskState::Running, &mut e3))
+                .unwrap_err(),
+            Error::LimitExceeded
+        );
+        assert_eq!(scheduler.len(), 3);
+        assert_eq!(scheduler.iter().map(|(id, _)| id).sum::<usize>(), 3);
+
+        scheduler.process();
+        assert_eq!(log.borrow().entries(), [0, 1, 2]);
+
+        assert_eq!(scheduler.get(2).unwrap().state, TaskState::Running);
+        assert_eq!(scheduler.remove(2), Ok(()));
+        assert_eq!(scheduler.get(2).err(), Some(Error::NoSuchTaskId));
+        assert_eq!(scheduler.get(3).err(), Some(Error::InvalidParameter));
+        assert_eq!(scheduler.len(), 2);
+    }
+
+    #[test]
+    fn scheduler_record_into() {
+        let mut e0 = FiniteExecuter { runs: 2 };
+        let mut e1: SomeExecuter = SomeExecuter {};
+        let mut e2: SomeExecuter = SomeExecuter {};
+        let mut scheduler: Scheduler<3> = Scheduler::new();
+
+        scheduler
+            .add(Task::new(TaskState::Running, &mut e0))
+            .unwrap();
+        scheduler
+            .add(Task::new(TaskState::Running, &mut e1))
+            .unwrap();
+        scheduler
+            .add_delayed(Task::new(TaskState::Running, &mut e2), 1)
+            .unwrap();
+
+        let mut log = [(0u64, 0usize); 7];
+        let mut len = 0usize;
+        for _ in 0..4 {
+            len += scheduler.record_into(&mut log[len..]);
+        }
+
+        assert_eq!(scheduler.cycles(), 4);
+        assert_eq!(
+            log,
+            [(0, 0), (0, 1), (1, 0), (1, 1), (1, 2), (2, 1), (2, 2)]
+        );
+    }
+
+    #[test]
+    fn scheduler_process_budget() {
+        let log = RefCell::new(Log::default());
+        let mut e0 = LogExecuter { log: &log };
+        let mut e1 = LogExecuter { log: &log };
+        let mut e2 = LogExecuter { log: &log };
+        let mut scheduler: Scheduler<4> = Scheduler::new();
+
+        scheduler
+            .add(Task::new(TaskState::Running, &mut e0))
+            .unwrap();
+        scheduler
+            .add(Task::new(TaskState::Running, &mut e1))
+            .unwrap();
+        scheduler
+            .add(Task::new(TaskState::Running, &mut e2))
+            .unwrap();
+
+        assert_eq!(scheduler.process_budget(2), 2);
+        assert_eq!(scheduler.process_budget(2), 2);
+        assert_eq!(scheduler.process_budget(0), 0);
+        assert_eq!(log.borrow().entries(), [0, 1, 2, 0]);
+    }
+
+    #[test]
+    fn scheduler_process_budget_cursor() {
+        let log = RefCell::new(Log::default());
+        let mut e0 = LogExecuter { log: &log };
+        let mut e1 = LogExecuter { log: &log };
+        let mut e2 = LogExecuter { log: &log };
+        let mut e3 = LogExecuter { log: &log };
+        let mut e4 = LogExecuter { log: &log };
+        let mut scheduler: Scheduler<5> = Scheduler::new();
+
+        for executer in [&mut e0 as &mut dyn Execute, &mut e1, &mut e2, &mut e3] {
+            scheduler
+                .add(Task::new(TaskState::Running, executer))
+                .unwrap();
+        }
+        assert_eq!(scheduler.remove(0), Ok(()));
+
+        assert_eq!(scheduler.process_budget(2), 2);
+        assert_eq!(log.borrow().entries(), [1, 2]);
+
+        assert_eq!(scheduler.compact(), 3);
+        assert_eq!(scheduler.add(Task::new(TaskState::Running, &mut e4)), Ok(3));
+        assert_eq!(scheduler.process_budget(2), 2);
+        assert_eq!(log.borrow().entries()[2..], [2, 3]);
+        assert_eq!(scheduler.process_budget(3), 3);
+        assert_eq!(log.borrow().entries()[4..], [0, 1, 2]);
+    }
+
+    #[test]
+    fn scheduler_compact_slot_state() {
+        let log = RefCell::new(Log::default());
+        let mut filler: SomeExecuter = SomeExecuter {};
+        let mut e1 = LogExecuter { log: &log };
+        let mut e2 = LogExecuter { log: &log };
+        let mut scheduler: Scheduler<3, u32> = Scheduler::new();
+
+        scheduler
+            .add(Task::new(TaskState::Running, &mut filler))
+            .unwrap();
+        scheduler
+            .add(Task::new(TaskState::Running, &mut e1))
+            .unwrap();
+        scheduler
+            .add(Task::new(TaskState::Running, &mut e2))
+            .unwrap();
+        assert_eq!(scheduler.set_min_rate(2, 1), Ok(()));
+        assert_eq!(scheduler.subscribe(2, 4), Ok(()));
+        *scheduler.scratch(2).unwrap() = 7;
+        scheduler.process();
+
+        assert_eq!(scheduler.remove(0), Ok(()));
+        assert_eq!(scheduler.compact(), 2);
+        assert_eq!(*scheduler.scratch(1).unwrap(), 7);
+        assert_eq!(*scheduler.scratch(0).unwrap(), 0);
+        assert_eq!(scheduler.runs(1), Ok(1));
+
+        assert_eq!(scheduler.process_budget(1), 1);
+        assert_eq!(log.borrow().entries()[2..], [1]);
+        assert_eq!(scheduler.publish(4, 9), Ok(1));
+        assert_eq!(scheduler.scratch(2).err(), Some(Error::NoSuchTaskId));
+    }
+
+    #[test]
+    fn scheduler_eviction_policy() {
+        let log = RefCell::new(Log::default());
+        let mut lowest =
+            |s: &Scheduler<'_, 2>| s.iter().min_by_key(|(_, t)| t.priority).map(|(id, _)| id);
+        let mut e0 = LogExecuter { log: &log };
+        let mut e1 = LogExecuter { log: &log };
+        let mut e2 = LogExecuter { log: &log };
+        let mut e3 = LogExecuter { log: &log };
+        let mut scheduler: Scheduler<2> = Scheduler::new();
+
+        for (executer, priority) in [(&mut e0 as &mut dyn Execute, 3u8), (&mut e1, 1)] {
+            let mut task = Task::new(TaskState::Running, executer);
+            task.priority = priority;
+            scheduler.add(task).unwrap();
+        }
+        assert_eq!(
+            scheduler.add(Task::new(TaskState::Running, &mut e2)).err(),
+            Some(Error::LimitExceeded)
+        );
+
+        scheduler.set_eviction_policy(&mut lowest);
+        let mut task = Task::new(TaskState::Running, &mut e3);
+        task.priority = 2;
+        assert_eq!(scheduler.add(task), Ok(1));
+        assert_eq!(scheduler.get(1).unwrap().priority, 2);
+
+        scheduler.process();
+        assert_eq!(log.borrow().entries(), [0, 1]);
+        assert_eq!(scheduler.len(), 2);
+    }
+
+    #[test]
+    fn scheduler_min_rate() {
+        let runs = [
+            core::cell::Cell::new(INVALID_ID),
+            core::cell::Cell::new(INVALID_ID),
+            core::cell::Cell::new(INVALID_ID),
+            core::cell::Cell::new(INVALID_ID),
+        ];
+        let mut e0 = IdExecuter { id: &runs[0] };
+        let mut e1 = IdExecuter { id: &runs[1] };
+        let mut e2 = IdExecuter { id: &runs[2] };
+        let mut e3 = IdExecuter { id: &runs[3] };
+        let mut scheduler: Scheduler<4> = Scheduler::new();
+
+        scheduler
+            .add(Task::new(TaskState::Running, &mut e0))
+            .unwrap();
+        scheduler
+            .add(Task::new(TaskState::Running, &mut e1))
+            .unwrap();
+        scheduler
+            .add(Task::new(TaskState::Running, &mut e2))
+            .unwrap();
+        let id = scheduler
+            .add(Task::new(TaskState::Running, &mut e3))
+            .unwrap();
+        assert_eq!(scheduler.set_min_rate(id, 2), Ok(()));
+        assert_eq!(scheduler.set_min_rate(7, 2), Err(Error::InvalidParameter));
+
+        let mut last_run = 0u64;
+        for cycle in 0..20u64 {
+            runs[id].set(INVALID_ID);
+            assert_eq!(scheduler.process_budget(1), 1);
+
+            if INVALID_ID != runs[id].get() {
+                last_run = cycle;
+            }
+            assert!(cycle - last_run < 2);
+        }
+    }
+
+    #[test]
+    fn scheduler_cycles_wrap() {
+        let runs = [
+            core::cell::Cell::new(INVALID_ID),
+            core::cell::Cell::new(INVALID_ID),
+        ];
+        let mut e0 = IdExecuter { id: &runs[0] };
+        let mut e1 = IdExecuter { id: &runs[1] };
+        let mut scheduler: Scheduler<2> = Scheduler::new();
+
+        scheduler
+            .add(Task::new(TaskState::Running, &mut e0))
+            .unwrap();
+        let id = scheduler
+            .add(Task::new(TaskState::Running, &mut e1))
+            .unwrap();
+
+        scheduler.cycles = u64::MAX - 4;
+        assert_eq!(scheduler.set_min_rate(id, 3), Ok(()));
+        assert_eq!(scheduler.set_min_rate(0, 3), Ok(()));
+
+        let mut since_run = [0u64; 2];
+        for _ in 0..12 {
+            runs[0].set(INVALID_ID);
+            runs[1].set(INVALID_ID);
+            assert_eq!(scheduler.process_budget(1), 1);
+
+            for (task, since) in since_run.iter_mut().enumerate() {
+                *since = if INVALID_ID != runs[task].get() {
+                    0
+                } else {
+                    *since + 1
+                };
+                assert!(*since < 3);
+            }
+        }
+        assert_eq!(scheduler.cycles(), 7);
+        assert_eq!(scheduler.cycles_since(u64::MAX), 8);
+    }
+
+    struct NeverSignal {}
+    impl crate::Signal for NeverSignal {
+        fn get_signal_state(&self) -> crate::SignalState {
+            crate::SignalState::NotSignaled
+        }
+    }
+
+    #[test]
+    fn scheduler_deadlocked_ids() {
+        let signal = NeverSignal {};
+        let mut e1: SomeExecuter = SomeExecuter {};
+        let mut e2: SomeExecuter = SomeExecuter {};
+        let mut scheduler: Scheduler<2> = Scheduler::new();
+
+        let mut t1 = Task::new(TaskState::Running, &mut e1);
+        t1.wait(&signal);
+        assert_eq!(scheduler.add(t1).unwrap(), 0);
+        assert_eq!(
+            scheduler
+                .add(Task::new(TaskState::Running, &mut e2))
+                .unwrap(),
+            1
+        );
+        scheduler.set_deadlock_threshold(Some(3));
+
+        for _ in 0..3 {
+            scheduler.process();
+        }
+        assert_eq!(scheduler.deadlocked_ids().count(), 0);
+
+        scheduler.process();
+        let mut ids = scheduler.deadlocked_ids();
+        assert_eq!(ids.next(), Some(0));
+        assert_eq!(ids.next(), None);
+    }
+
+    struct InitExecuter<'a> {
+        runs: usize,
+        done: &'a core::cell::Cell<bool>,
+    }
+    impl Execute for InitExecuter<'_> {
+        fn execute(&mut self, _id: TaskId) -> TaskControl {
+            self.runs += 1;
+            if 5 == self.runs {
+                self.done.set(true);
+            }
+            TaskControl::Continue
+        }
+    }
+
+    #[test]
+    fn scheduler_run_while() {
+        let done = core::cell::Cell::new(false);
+        let mut e1 = InitExecuter {
+            runs: 0,
+            done: &done,
+        };
+        let mut scheduler: Scheduler<2> = Scheduler::new();
+
+        scheduler
+            .add(Task::new(TaskState::Running, &mut e1))
+            .unwrap();
+
+        assert_eq!(scheduler.run_while(100, || !done.get()), 5);
+        assert!(done.get());
+        assert_eq!(scheduler.cycles(), 5);
+        assert_eq!(scheduler.run_while(100, || !done.get()), 0);
+    }
+
+    struct SenderExecuter {
+        to: TaskId,
+    }
+    impl Execute for SenderExecuter {
+        fn execute(&mut self, _id: TaskId) -> TaskControl {
+            TaskControl::Continue
+        }
+
+        fn execute_with(&mut self, _id: TaskId, ctx: &mut Context) -> TaskControl {
+            assert_eq!(ctx.send(self.to, 10), Ok(()));
+            assert_eq!(ctx.send(self.to, 20), Ok(()));
+            TaskControl::Continue
+        }
+    }
+
+    struct ReceiverExecuter<'a> {
+        log: &'a RefCell<Log>,
+    }
+    impl Execute for ReceiverExecuter<'_> {
+        fn execute(&mut self, _id: TaskId) -> TaskControl {
+            TaskControl::Continue
+        }
+
+        fn execute_with(&mut self, _id: TaskId, ctx: &mut Context) -> TaskControl {
+            let mut log = self.log.borrow_mut();
+            while let Some(msg) = ctx.receive() {
+                let len = log.len;
+                log.ids[len] = msg as TaskId;
+                log.len += 1;
+            }
+            TaskControl::Continue
+        }
+    }
+
+    /// Records the number of tasks and the state of task 1 seen by its view.
+    struct SupervisorExecuter<'a> {
+        seen: &'a RefCell<Log>,
+    }
+    impl Execute for SupervisorExecuter<'_> {
+        fn execute(&mut self, _id: TaskId) -> TaskControl {
+            TaskControl::Continue
+        }
+
+        fn execute_with(&mut self, id: TaskId, ctx: &mut Context) -> TaskControl {
+            let view = ctx.view().unwrap();
+            let mut seen = self.seen.borrow_mut();
+            let len = seen.len;
+            seen.ids[len] = view.len();
+            seen.ids[len + 1] = view.state_of(1).map_or(INVALID_ID, |s| s.as_u8() as TaskId);
+            seen.len += 2;
+            assert_eq!(view.state_of(id), Some(TaskState::Running));
+            assert_eq!(view.name_of(1), Some("worker"));
+            TaskControl::Continue
+        }
+    }
+
+    #[test]
+    fn scheduler_view() {
+        let seen = RefCell::new(Log::default());
+        let mut supervisor = SupervisorExecuter { seen: &seen };
+        let mut worker = SomeExecuter {};
+        let mut scheduler: Scheduler<3> = Scheduler::new();
+        scheduler
+            .add(Task::new(TaskState::Running, &mut supervisor))
+            .unwrap();
+        scheduler
+            .add(Task::named(TaskState::Suspended, "worker", &mut worker))
+            .unwrap();
+
+        scheduler.process();
+        scheduler.get(1).unwrap().resume();
+        scheduler.process();
+
+        let suspended = TaskState::Suspended.as_u8() as TaskId;
+        let running = TaskState::Running.as_u8() as TaskId;
+        assert_eq!(seen.borrow().entries(), [2, suspended, 2, running]);
+        assert!(Context::<()>::detached(0).view().is_none());
+    }
+
+    /// Counts its executions, the task with ID 0 defers its action in its
+    /// first execution.
+    struct DeferringExecuter<'e> {
+        executions: &'e Cell<usize>,
+        deferred: bool,
+    }
+    impl Execute for DeferringExecuter<'_> {
+        fn execute(&mut self, _id: TaskId) -> TaskControl {
+            TaskControl::Continue
+        }
+
+        fn execute_with(&mut self, id: TaskId, ctx: &mut Context) -> TaskControl {
+            self.executions.set(self.executions.get() + 1);
+            if (0 == id) && !self.deferred {
+                self.deferred = true;
+                assert_eq!(ctx.defer(), Ok(()));
+            }
+            TaskControl::Continue
+        }
+    }
+
+    #[test]
+    fn scheduler_defer() {
+        let executions = Cell::new(0usize);
+        let mut seen = [0usize; 2];
+        let mut calls = 0usize;
+        let mut action = || {
+            seen[calls] = executions.get();
+            calls += 1;
+        };
+        let mut e0 = DeferringExecuter {
+            executions: &executions,
+            deferred: false,
+        };
+        let mut e1 = DeferringExecuter {
+            executions: &executions,
+            deferred: false,
+        };
+        let mut scheduler: Scheduler<2> = Scheduler::new();
+        scheduler
+            .add(Task::new(TaskState::Running, &mut e0))
+            .unwrap();
+        scheduler
+            .add(Task::new(TaskState::Running, &mut e1))
+            .unwrap();
+        assert_eq!(scheduler.set_deferred_action(0, &mut action), Ok(()));
+
+        scheduler.process();
+        scheduler.process();
+        assert_eq!(executions.get(), 4);
+
+        assert_eq!(calls, 1);
+        assert_eq!(seen, [2, 0]);
+        assert_eq!(
+            Context::<()>::detached(0).defer(),
+            Err(Error::InvalidParameter)
+        );
+    }
+
+    #[test]
+    fn scheduler_messages() {
+        let log = RefCell::new(Log::default());
+        let mut receiver = ReceiverExecuter { log: &log };
+        let mut sender = SenderExecuter { to: 0 };
+        let mut scheduler: Scheduler<2> = Scheduler::new();
+
+        assert_eq!(
+            scheduler
+                .add(Task::new(TaskState::Suspended, &mut receiver))
+                .unwrap(),
+            0
+        );
+        assert_eq!(
+            scheduler
+                .add(Task::new(TaskState::Running, &mut sender))
+                .unwrap(),
+            1
+        );
+
+        scheduler.process();
+        scheduler.get(1).unwrap().suspend();
+        assert!(log.borrow().entries().is_empty());
+
+        scheduler.get(0).unwrap().resume();
+        scheduler.process();
+        assert_eq!(log.borrow().entries(), [10, 20]);
+
+        for msg in 0..INBOX_SIZE as u32 {
+            assert_eq!(scheduler.send(1, 0, msg), Ok(()));
+        }
+        assert_eq!(scheduler.send(1, 0, 99), Err(Error::LimitExceeded));
+        assert_eq!(scheduler.send(1, 5, 99), Err(Error::InvalidParameter));
+    }
+
+    #[test]
+    fn scheduler_run_init_phase() {
+        let log = RefCell::new(Log::default());
+        let mut e0 = LogExecuter { log: &log };
+        let mut e1 = LogExecuter { log: &log };
+        let mut e2 = LogExecuter { log: &log };
+        let mut e3 = LogExecuter { log: &log };
+        let mut scheduler: Scheduler<4> = Scheduler::new();
+        let mut init_low = Task::new(TaskState::Running, &mut e0);
+        init_low.init = true;
+        init_low.priority = 1;
+        let mut init_high = Task::new(TaskState::Running, &mut e2);
+        init_high.init = true;
+        init_high.priority = 5;
+        let mut init_equal = Task::new(TaskState::Running, &mut e3);
+        init_equal.init = true;
+        init_equal.priority = 1;
+        scheduler.add(init_low).unwrap();
+        scheduler
+            .add(Task::new(TaskState::Running, &mut e1))
+            .unwrap();
+        scheduler.add(init_high).unwrap();
+        scheduler.add(init_equal).unwrap();
+
+        assert_eq!(scheduler.run_init_phase(), 3);
+        assert_eq!(log.borrow().entries(), [2, 0, 3]);
+        assert_eq!(scheduler.get(0).unwrap().state, TaskState::Suspended);
+        assert!(!scheduler.get(2).unwrap().init);
+        assert_eq!(scheduler.cycles(), 0);
+
+        assert_eq!(scheduler.run_init_phase(), 0);
+        scheduler.process();
+        assert_eq!(log.borrow().entries(), [2, 0, 3, 1]);
+    }
+
+    struct CancelExecuter {
+        runs: usize,
+        cleaned_up: bool,
+    }
+    impl Execute for CancelExecuter {
+        fn execute(&mut self, _id: TaskId) -> TaskControl {
+            TaskControl::Continue
+        }
+
+        fn execute_with(&mut self, _id: TaskId, ctx: &mut Context) -> TaskControl {
+            self.runs += 1;
+            if ctx.is_cancelled() {
+                self.cleaned_up = true;
+                return TaskControl::Remove;
+            }
+            TaskControl::Continue
+        }
+    }
+
+    #[test]
+    fn scheduler_cancel() {
+        let mut executer = CancelExecuter {
+            runs: 0,
+            cleaned_up: false,
+        };
+        {
+            let mut scheduler: Scheduler<2> = Scheduler::new();
+            let id = scheduler
+                .add(Task::new(TaskState::Running, &mut executer))
+                .unwrap();
+
+            scheduler.process();
+            assert_eq!(scheduler.cancel(id), Ok(()));
+            assert!(scheduler.get(id).unwrap().is_cancelled());
+            assert_eq!(scheduler.cancel(1), Err(Error::NoSuchTaskId));
+
+            scheduler.process();
+            assert!(scheduler.get(id).is_err());
+            assert!(scheduler.is_empty());
+        }
+        assert_eq!(executer.runs, 2);
+        assert!(executer.cleaned_up);
+    }
+
+    #[test]
+    fn scheduler_sub_scheduler() {
+        let log = RefCell::new(Log::default());
+        let mut e0 = LogExecuter { log: &log };
+        let mut e1 = LogExecuter { log: &log };
+        let mut child: SubSchedulerExecuter<2> = SubSchedulerExecuter::new(Scheduler::new());
+        child
+            .scheduler_mut()
+            .add(Task::new(TaskState::Running, &mut e0))
+            .unwrap();
+        child
+            .scheduler_mut()
+            .add(Task::new(TaskState::Running, &mut e1))
+            .unwrap();
+
+        {
+            let mut parent: Scheduler<2> = Scheduler::new();
+            let id = parent
+                .add(Task::new(TaskState::Running, &mut child))
+                .unwrap();
+
+            parent.process();
+            assert_eq!(log.borrow().entries(), [0, 1]);
+
+            parent.get(id).unwrap().suspend();
+            parent.process();
+            parent.process();
+            assert_eq!(log.borrow().entries(), [0, 1]);
+
+            parent.get(id).unwrap().resume();
+            parent.process();
+            assert_eq!(log.borrow().entries(), [0, 1, 0, 1]);
+        }
+        assert_eq!(child.scheduler().cycles(), 2);
+    }
+
+    #[test]
+    fn scheduler_clear_ordered() {
+        let mut e0 = SomeExecuter {};
+        let mut e1 = SomeExecuter {};
+        let mut e2 = SomeExecuter {};
+        let mut e3 = SomeExecuter {};
+        let mut e4 = SomeExecuter {};
+        let mut order = [INVALID_ID; 4];
+        let mut count = 0usize;
+        let mut scheduler: Scheduler<4> = Scheduler::new();
+
+        scheduler
+            .add(Task::new(TaskState::Running, &mut e0))
+            .unwrap();
+        scheduler
+            .add(Task::new(TaskState::Running, &mut e1))
+            .unwrap();
+        scheduler
+            .add(Task::new(TaskState::Running, &mut e2))
+            .unwrap();
+        scheduler.remove(0).unwrap();
+        scheduler
+            .add_at(3, Task::new(TaskState::Running, &mut e3))
+            .unwrap();
+        scheduler
+            .add(Task::new(TaskState::Running, &mut e4))
+            .unwrap();
+
+        scheduler.clear_ordered(|id, task| {
+            assert_eq!(task.state, TaskState::Running);
+            order[count] = id;
+            count += 1;
+        });
+        assert_eq!(order, [0, 3, 2, 1]);
+        assert!(scheduler.is_empty());
+    }
+
+    #[test]
+    fn scheduler_step() {
+        let log = RefCell::new(Log::default());
+        let mut e0 = LogExecuter { log: &log };
+        let mut e1 = LogExecuter { log: &log };
+        let mut e2 = LogExecuter { log: &log };
+        let mut e3 = LogExecuter { log: &log };
+        let mut e4 = LogExecuter { log: &log };
+        let mut scheduler: Scheduler<6> = Scheduler::new();
+        scheduler
+            .add(Task::new(TaskState::Running, &mut e0))
+            .unwrap();
+        scheduler
+            .add(Task::new(TaskState::Suspended, &mut e1))
+            .unwrap();
+        scheduler
+            .add(Task::new(TaskState::Running, &mut e2))
+            .unwrap();
+        scheduler
+            .add(Task::new(TaskState::Running, &mut e3))
+            .unwrap();
+        scheduler
+            .add(Task::new(TaskState::Running, &mut e4))
+            .unwrap();
+
+        assert_eq!(scheduler.step(), Some(0));
+        assert_eq!(scheduler.step(), Some(2));
+        assert_eq!(scheduler.step(), Some(3));
+        assert_eq!(scheduler.step(), Some(4));
+        assert_eq!(log.borrow().entries(), [0, 2, 3, 4]);
+
+        assert!(!scheduler.is_step_mode());
+        scheduler.set_step_mode(true);
+        assert!(scheduler.is_step_mode());
+        scheduler.process();
+        assert_eq!(log.borrow().entries(), [0, 2, 3, 4, 0]);
+
+        scheduler.set_step_mode(false);
+        scheduler.process();
+        assert_eq!(log.borrow().entries()[5..], [0, 2, 3, 4]);
+    }
+
+    #[test]
+    fn scheduler_process_random() {
+        let log = RefCell::new(Log::default());
+        let mut e0 = LogExecuter { log: &log };
+        let mut e1 = LogExecuter { log: &log };
+        let mut e2 = LogExecuter { log: &log };
+        let mut e3 = LogExecuter { log: &log };
+        let mut s0 = SomeExecuter {};
+        let mut s1 = SomeExecuter {};
+        let mut s2 = SomeExecuter {};
+        let mut s3 = SomeExecuter {};
+        let mut scheduler: Scheduler<4> = Scheduler::new();
+        let mut reference: Scheduler<4> = Scheduler::new();
+        let mut important = Task::new(TaskState::Running, &mut e2);
+        important.priority = 3;
+        let mut reference_important = Task::new(TaskState::Running, &mut s2);
+        reference_important.priority = 3;
+
+        scheduler
+            .add(Task::new(TaskState::Running, &mut e0))
+            .unwrap();
+        scheduler
+            .add(Task::new(TaskState::Suspended, &mut e1))
+            .unwrap();
+        scheduler.add(important).unwrap();
+        scheduler
+            .add(Task::new(TaskState::Running, &mut e3))
+            .unwrap();
+        reference
+            .add(Task::new(TaskState::Running, &mut s0))
+            .unwrap();
+        reference
+            .add(Task::new(TaskState::Suspended, &mut s1))
+            .unwrap();
+        reference.add(reference_important).unwrap();
+        reference
+            .add(Task::new(TaskState::Running, &mut s3))
+            .unwrap();
+        scheduler.set_random_seed(7);
+        reference.set_random_seed(7);
+
+        let mut counts = [0usize; 4];
+        for _ in 0..32 {
+            let id = scheduler.process_random().unwrap();
+            assert_eq!(reference.process_random(), Some(id));
+            counts[id] += 1;
+        }
+
+        assert_eq!(counts[1], 0);
+        assert!(0 < counts[0]);
+        assert!(0 < counts[3]);
+        assert!(counts[2] > counts[0] + counts[3]);
+        assert_eq!(log.borrow().len, 32);
+        assert_eq!(scheduler.cycles(), 32);
+    }
+
+    #[test]
+    fn scheduler_process_random_distribution() {
+        let mut executers = [
+            SomeExecuter {},
+            SomeExecuter {},
+            SomeExecuter {},
+            SomeExecuter {},
+        ];
+        let mut scheduler: Scheduler<4> = Scheduler::new();
+        for executer in executers.iter_mut() {
+            scheduler
+                .add(Task::new(TaskState::Running, executer))
+                .unwrap();
+        }
+        scheduler.set_random_seed(1);
+
+        let mut counts = [0usize; 4];
+        let mut repeats = 0usize;
+        let mut last = INVALID_ID;
+        for _ in 0..4000 {
+            let id = scheduler.process_random().unwrap();
+            counts[id] += 1;
+            if id == last {
+                repeats += 1;
+            }
+            last = id;
+        }
+
+        assert!(counts.iter().all(|count| (900..1100).contains(count)));
+        assert!((900..1100).contains(&repeats));
+    }
+
+    #[test]
+    fn scheduler_export_graph() {
+        let mut e0 = SomeExecuter {};
+        let mut e1 = SomeExecuter {};
+        let mut e2 = SomeExecuter {};
+        let mut scheduler: Scheduler<3> = Scheduler::new();
+        let mut output = Task::named(TaskState::Running, "output", &mut e2);
+        output.phase = 2;
+        scheduler
+            .add(Task::named(TaskState::Running, "input", &mut e0))
+            .unwrap();
+        scheduler
+            .add(Task::new(TaskState::Suspended, &mut e1))
+            .unwrap();
+        scheduler.add(output).unwrap();
+        scheduler.on_complete(0, 1).unwrap();
+        scheduler.wait_for_runs(2, 0, 3).unwrap();
+
+        let mut out = BufWriter {
+            buf: [0; 256],
+            len: 0,
+        };
+        assert_eq!(scheduler.export_graph(&mut out), Ok(()));
+
+        let graph = core::str::from_utf8(&out.buf[..out.len]).unwrap();
+        let mut lines = graph.lines();
+        assert_eq!(lines.next(), Some("digraph tasks {"));
+        assert_eq!(lines.next(), Some("  t0 [label=\"0 input\\nphase 0\"];"));
+        assert_eq!(lines.next(), Some("  t1 [label=\"1 -\\nphase 0\"];"));
+        assert_eq!(lines.next(), Some("  t2 [label=\"2 output\\nphase 2\"];"));
+        assert_eq!(lines.next(), Some("  t0 -> t1 [label=\"on_complete\"];"));
+        assert_eq!(lines.next(), Some("  t0 -> t2 [label=\"runs 3\"];"));
+        assert_eq!(lines.next(), Some("}"));
+        assert_eq!(lines.next(), None);
+    }
+
+    #[test]
+    fn scheduler_priority_ceiling() {
+        let log = RefCell::new(Log::default());
+        let mut e0 = LogExecuter { log: &log };
+        let mut e1 = LogExecuter { log: &log };
+        let mut e2 = LogExecuter { log: &log };
+        let mut scheduler: Scheduler<3> = Scheduler::new();
+        let mut low = Task::new(TaskState::Running, &mut e0);
+        low.priority = 1;
+        let mut mid = Task::new(TaskState::Running, &mut e1);
+        mid.priority = 3;
+        let mut high = Task::new(TaskState::Running, &mut e2);
+        high.priority = 7;
+        scheduler.add(low).unwrap();
+        scheduler.add(mid).unwrap();
+        scheduler.add(high).unwrap();
+
+        assert_eq!(scheduler.enter_critical(0, 5), Ok(()));
+        scheduler.process();
+        assert_eq!(log.borrow().entries(), [0, 1, 2]);
+
+        scheduler.get(2).unwrap().suspend();
+        assert_eq!(scheduler.process_priority(), Some(0));
+        assert_eq!(scheduler.process_priority(), Some(0));
+
+        assert_eq!(scheduler.exit_critical(0), Ok(()));
+        assert_eq!(scheduler.process_priority(), Some(1));
+        assert_eq!(log.borrow().entries(), [0, 1, 2, 0, 0, 1]);
+    }
+
+    #[test]
+    fn scheduler_priority_ceiling_idle_holder() {
+        let log = RefCell::new(Log::default());
+        let mut e0 = LogExecuter { log: &log };
+        let mut e1 = LogExecuter { log: &log };
+        let mut scheduler: Scheduler<2> = Scheduler::new();
+        let mut holder = Task::new(TaskState::Running, &mut e0);
+        holder.priority = 1;
+        let mut other = Task::new(TaskState::Running, &mut e1);
+        other.priority = 3;
+        scheduler.add(holder).unwrap();
+        scheduler.add(other).unwrap();
+        assert_eq!(scheduler.enter_critical(0, 5), Ok(()));
+        assert_eq!(scheduler.process_priority(), Some(0));
+
+        // a holder which can't run doesn't block the others
+        for state in [
+            TaskState::Suspended,
+            TaskState::Faulted,
+            TaskState::Waiting,
+            TaskState::Delayed,
+        ] {
+            scheduler.get(0).unwrap().state = state;
+            assert_eq!(scheduler.process_priority(), Some(1));
+        }
+
+        scheduler.get(0).unwrap().state = TaskState::Running;
+        assert_eq!(scheduler.process_priority(), Some(0));
+        assert_eq!(log.borrow().entries(), [0, 1, 1, 1, 1, 0]);
+    }
+
+    #[test]
+    fn scheduler_publish() {
+        let log = RefCell::new(Log::default());
+        let signal = NeverSignal {};
+        let mut r0 = ReceiverExecuter { log: &log };
+        let mut r1 = ReceiverExecuter { log: &log };
+        let mut other = ReceiverExecuter { log: &log };
+        let mut scheduler: Scheduler<3> = Scheduler::new();
+        let mut waiting = Task::new(TaskState::Running, &mut r0);
+        waiting.wait(&signal);
+        scheduler.add(waiting).unwrap();
+        scheduler
+            .add(Task::new(TaskState::Running, &mut r1))
+            .unwrap();
+        scheduler
+            .add(Task::new(TaskState::Running, &mut other))
+            .unwrap();
+
+        assert_eq!(scheduler.subscribe(0, 5), Ok(()));
+        assert_eq!(scheduler.subscribe(1, 5), Ok(()));
+        assert_eq!(scheduler.subscribe(2, 6), Ok(()));
+        assert_eq!(
+            scheduler.subscribe(2, EVENT_COUNT),
+            Err(Error::InvalidParameter)
+        );
+
+        scheduler.process();
+        assert!(log.borrow().entries().is_empty());
+
+        assert_eq!(scheduler.publish(5, 77), Ok(2));
+        assert_eq!(scheduler.get(0).unwrap().state, TaskState::Running);
+        scheduler.process();
+        assert_eq!(log.borrow().entries(), [77, 77]);
+
+        assert_eq!(scheduler.unsubscribe(1, 5), Ok(()));
+        assert_eq!(scheduler.publish(5, 78), Ok(1));
+        assert_eq!(
+            scheduler.publish(EVENT_COUNT, 0),
+            Err(Error::InvalidParameter)
+        );
+        scheduler.process();
+        assert_eq!(log.borrow().entries()[2..], [78]);
+    }
+
+    #[cfg(feature = "critical-section")]
+    struct MockCriticalSection<'a> {
+        locked: &'a core::cell::Cell<bool>,
+        entries: core::cell::Cell<usize>,
+    }
+
+    #[cfg(feature = "critical-section")]
+    impl CriticalSection for MockCriticalSection<'_> {
+        fn with<R>(&self, f: impl FnOnce() -> R) -> R {
+            assert!(!self.locked.get());
+            self.locked.set(true);
+            self.entries.set(self.entries.get() + 1);
+            let result = f();
+            self.locked.set(false);
+            result
+        }
+    }
+
+    #[cfg(feature = "critical-section")]
+    struct UnlockedExecuter<'a> {
+        locked: &'a core::cell::Cell<bool>,
+        runs: usize,
+    }
+
+    #[cfg(feature = "critical-section")]
+    impl Execute for UnlockedExecuter<'_> {
+        fn execute(&mut self, _id: TaskId) -> TaskControl {
+            assert!(!self.locked.get());
+            self.runs += 1;
+            TaskControl::Continue
+        }
+    }
+
+    #[cfg(feature = "critical-section")]
+    #[test]
+    fn scheduler_process_cs() {
+        let locked = core::cell::Cell::new(false);
+        let cs = MockCriticalSection {
+            locked: &locked,
+            entries: core::cell::Cell::new(0),
+        };
+        let mut e1 = UnlockedExecuter {
+            locked: &locked,
+            runs: 0,
+        };
+        let mut e2: SomeExecuter = SomeExecuter {};
+
+        {
+            let mut scheduler: Scheduler<2> = Scheduler::new();
+            scheduler
+                .add(Task::new(TaskState::Running, &mut e1))
+                .unwrap();
+            scheduler
+                .add(Task::new(TaskState::Suspended, &mut e2))
+                .unwrap();
+
+            scheduler.process_cs(&cs);
+            scheduler.process_cs(&cs);
+            assert_eq!(cs.entries.get(), 6);
+            assert!(!locked.get());
+        }
+
+        assert_eq!(e1.runs, 2);
+    }
+
+    #[test]
+    fn scheduler_reserve_id() {
+        let mut e1: SomeExecuter = SomeExecuter {};
+        let mut e2: SomeExecuter = SomeExecuter {};
+        let mut e3: SomeExecuter = SomeExecuter {};
+        let mut scheduler: Scheduler<3> = Scheduler::new();
+
+        assert_eq!(scheduler.reserve(0), Ok(()));
+        assert_eq!(scheduler.reserve(3), Err(Error::InvalidParameter));
+        assert_eq!(
+            scheduler
+                .add(Task::new(TaskState::Running, &mut e1))
+                .unwrap(),
+            1
+        );
+        assert_eq!(
+            scheduler.add_at(0, Task::new(TaskState::Running, &mut e2)),
+            Ok(())
+        );
+        assert!(scheduler.get(0).is_ok());
+        assert_eq!(scheduler.remove(0), Ok(()));
+
+        assert_eq!(scheduler.release(0), Ok(()));
+        assert_eq!(
+            scheduler
+                .add(Task::new(TaskState::Running, &mut e3))
+                .unwrap(),
+            0
+        );
+    }
+
+    struct StepClock {
+        now: core::cell::Cell<u64>,
+    }
+    impl crate::Clock for StepClock {
+        fn now(&self) -> u64 {
+            self.now.get()
+        }
+    }
+
+    #[test]
+    fn scheduler_order_changed() {
+        let mut e0 = SomeExecuter {};
+        let mut e1 = SomeExecuter {};
+        let mut e2 = SomeExecuter {};
+        let mut scheduler: Scheduler<3> = Scheduler::new();
+        for executer in [&mut e0 as &mut dyn Execute, &mut e1, &mut e2] {
+            scheduler
+                .add(Task::new(TaskState::Running, executer))
+                .unwrap();
+        }
+
+        scheduler.process();
+        assert!(!scheduler.order_changed());
+        for _ in 0..3 {
+            scheduler.process();
+            assert!(!scheduler.order_changed());
+        }
+
+        scheduler.get(1).unwrap().suspend();
+        scheduler.process();
+        assert!(scheduler.order_changed());
+        scheduler.process();
+        assert!(!scheduler.order_changed());
+
+        scheduler.get(1).unwrap().resume();
+        scheduler.process();
+        assert!(scheduler.order_changed());
+    }
+
+    #[test]
+    fn scheduler_cycle_jitter() {
+        let clock = StepClock {
+            now: core::cell::Cell::new(100),
+        };
+        let mut scheduler: Scheduler<2> = Scheduler::new();
+
+        for step in [0u64, 10, 7, 12, 10] {
+            clock.now.set(clock.now.get() + step);
+            scheduler.process_timed(&clock);
+        }
+
+        assert_eq!(scheduler.cycle_jitter(), (7, 12, 10));
+        assert_eq!(scheduler.cycles(), 5);
+    }
+
+    struct ChunkExecuter<'a> {
+        clock: &'a StepClock,
+        chunks: usize,
+        yields: usize,
+    }
+    impl Execute for ChunkExecuter<'_> {
+        fn execute(&mut self, _id: TaskId) -> TaskControl {
+            TaskControl::Continue
+        }
+
+        fn execute_with(&mut self, _id: TaskId, ctx: &mut Context) -> TaskControl {
+            for _ in 0..10 {
+                self.chunks += 1;
+                self.clock.now.set(self.clock.now.get() + 1);
+
+                if ctx.should_yield() {
+                    self.yields += 1;
+                    return TaskControl::Yield;
+                }
+            }
+            TaskControl::Continue
+        }
+    }
+
+    #[test]
+    fn scheduler_yield_budget() {
+        let clock = StepClock {
+            now: core::cell::Cell::new(0),
+        };
+        let mut e1 = ChunkExecuter {
+            clock: &clock,
+            chunks: 0,
+            yields: 0,
+        };
+
+        {
+            let mut scheduler: Scheduler<1> = Scheduler::new();
+            scheduler
+                .add(Task::new(TaskState::Running, &mut e1))
+                .unwrap();
+
+            scheduler.process();
+            scheduler.set_yield_budget(&clock, 3);
+            scheduler.process();
+            scheduler.clear_yield_budget();
+            scheduler.process();
+            assert!(scheduler.get(0).is_ok());
+        }
+
+        assert_eq!(e1.chunks, 23);
+        assert_eq!(e1.yields, 1);
+    }
+
+    struct CostExecuter<'a> {
+        clock: &'a StepClock,
+        cost: u64,
+    }
+    impl Execute for CostExecuter<'_> {
+        fn execute(&mut self, _id: TaskId) -> TaskControl {
+            self.clock.now.set(self.clock.now.get() + self.cost);
+            TaskControl::Continue
+        }
+    }
+
+    #[test]
+    fn scheduler_cpu_share() {
+        let clock = StepClock {
+            now: core::cell::Cell::new(0),
+        };
+        let mut e0 = CostExecuter {
+            clock: &clock,
+            cost: 3,
+        };
+        let mut e1 = CostExecuter {
+            clock: &clock,
+            cost: 1,
+        };
+        let mut scheduler: Scheduler<3> = Scheduler::new();
+        scheduler
+            .add(Task::new(TaskState::Running, &mut e0))
+            .unwrap();
+        scheduler
+            .add(Task::new(TaskState::Running, &mut e1))
+            .unwrap();
+
+        scheduler.process();
+        assert_eq!(scheduler.cpu_share(0), Ok(0));
+
+        scheduler.set_cpu_clock(&clock);
+        for _ in 0..4 {
+            scheduler.process();
+        }
+        assert_eq!(scheduler.cpu_share(0), Ok(750));
+        assert_eq!(scheduler.cpu_share(1), Ok(250));
+        assert_eq!(scheduler.cpu_share(2), Err(Error::NoSuchTaskId));
+
+        scheduler.reset_cpu_stats();
+        assert_eq!(scheduler.cpu_share(0), Ok(0));
+        scheduler.get(0).unwrap().suspend();
+        scheduler.process();
+        assert_eq!(scheduler.cpu_share(0), Ok(0));
+        assert_eq!(scheduler.cpu_share(1), Ok(1000));
+
+        scheduler.get(0).unwrap().resume();
+        scheduler.process();
+        assert_eq!(scheduler.cpu_share(0), Ok(600));
+        assert_eq!(scheduler.remove(1), Ok(()));
+        assert_eq!(scheduler.cpu_share(0), Ok(600));
+    }
+
+    /// Records the CPU share of task 0 seen by its view.
+    struct ShareExecuter<'a> {
+        seen: &'a Cell<Option<u32>>,
+    }
+    impl Execute for ShareExecuter<'_> {
+        fn execute(&mut self, _id: TaskId) -> TaskControl {
+            TaskControl::Continue
+        }
+
+        fn execute_with(&mut self, _id: TaskId, ctx: &mut Context) -> TaskControl {
+            self.seen
+                .set(ctx.view().and_then(|view| view.cpu_share_of(0)));
+            TaskControl::Continue
+        }
+    }
+
+    #[test]
+    fn scheduler_view_cpu_share() {
+        let clock = StepClock {
+            now: core::cell::Cell::new(0),
+        };
+        let seen = Cell::new(None);
+        let mut e0 = CostExecuter {
+            clock: &clock,
+            cost: 3,
+        };
+        let mut e1 = CostExecuter {
+            clock: &clock,
+            cost: 1,
+        };
+        let mut watcher = ShareExecuter { seen: &seen };
+        let mut scheduler: Scheduler<3> = Scheduler::new();
+        scheduler
+            .add(Task::new(TaskState::Running, &mut e0))
+            .unwrap();
+        scheduler
+            .add(Task::new(TaskState::Running, &mut e1))
+            .unwrap();
+        scheduler
+            .add(Task::new(TaskState::Running, &mut watcher))
+            .unwrap();
+
+        scheduler.set_cpu_clock(&clock);
+        scheduler.process();
+        assert_eq!(seen.get(), Some(750));
+
+        let shared = &scheduler;
+        assert_eq!(shared.cpu_share(0), Ok(750));
+        assert_eq!(shared.runs(0), Ok(1));
+
+        assert_eq!(scheduler.remove(0), Ok(()));
+        scheduler.process();
+        assert_eq!(seen.get(), None);
+    }
+
+    struct SelfRemovingExecuter<'a> {
+        log: &'a RefCell<Log>,
+        runs: usize,
+    }
+    impl Execute for SelfRemovingExecuter<'_> {
+        fn execute(&mut self, id: TaskId) -> TaskControl {
+            self.log.borrow_mut().push(id);
+            self.runs += 1;
+
+            if 3 == self.runs {
+                TaskControl::Remove
+            } else {
+                TaskControl::Continue
+            }
+        }
+    }
+
+    #[test]
+    fn scheduler_self_removal() {
+        let log = RefCell::new(Log::default());
+        let mut e0 = LogExecuter { log: &log };
+        let mut e1 = SelfRemovingExecuter { log: &log, runs: 0 };
+        let mut e2 = LogExecuter { log: &log };
+        let mut e3: SomeExecuter = SomeExecuter {};
+        let mut scheduler: Scheduler<3> = Scheduler::new();
+
+        scheduler
+            .add(Task::new(TaskState::Running, &mut e0))
+            .unwrap();
+        scheduler
+            .add(Task::new(TaskState::Running, &mut e1))
+            .unwrap();
+        scheduler
+            .add(Task::new(TaskState::Running, &mut e2))
+            .unwrap();
+
+        for _ in 0..11 {
+            assert_eq!(scheduler.process_budget(1), 1);
+        }
+        assert_eq!(log.borrow().entries(), [0, 1, 2, 0, 1, 2, 0, 1, 2, 0, 2]);
+        assert_eq!(scheduler.len(), 2);
+        assert!(scheduler.remove(1).is_err());
+
+        assert_eq!(
+            scheduler
+                .add(Task::new(TaskState::Running, &mut e3))
+                .unwrap(),
+            1
+        );
+    }
+
+    #[test]
+    fn scheduler_wrong_scheduler_handle() {
+        let mut e1: SomeExecuter = SomeExecuter {};
+        let mut e2: SomeExecuter = SomeExecuter {};
+        let mut a: Scheduler<2> = Scheduler::new();
+        let mut b: Scheduler<2> = Scheduler::new();
+
+        let id = a.add(Task::new(TaskState::Running, &mut e1)).unwrap();
+        b.add(Task::new(TaskState::Running, &mut e2)).unwrap();
+        let handle = a.handle(id).unwrap();
+        assert_eq!(handle.id(), id);
+
+        assert_eq!(b.get_by_handle(handle).err(), Some(Error::WrongScheduler));
+        assert_eq!(b.remove_by_handle(handle), Err(Error::WrongScheduler));
+        assert_eq!(b.len(), 1);
+
+        assert!(a.get_by_handle(handle).is_ok());
+        assert_eq!(a.remove_by_handle(handle), Ok(()));
+        assert_eq!(a.remove_by_handle(handle), Err(Error::NoSuchTaskId));
+    }
+
+    #[test]
+    fn scheduler_stale_handle() {
+        let mut e0: SomeExecuter = SomeExecuter {};
+        let mut e1: SomeExecuter = SomeExecuter {};
+        let mut e2: SomeExecuter = SomeExecuter {};
+        let mut e3: SomeExecuter = SomeExecuter {};
+        let mut scheduler: Scheduler<3> = Scheduler::new();
+
+        scheduler
+            .add(Task::new(TaskState::Running, &mut e0))
+            .unwrap();
+        scheduler
+            .add(Task::new(TaskState::Running, &mut e1))
+            .unwrap();
+        scheduler
+            .add(Task::new(TaskState::Suspended, &mut e2))
+            .unwrap();
+
+        // a removed task's handle doesn't resolve to a task reusing its slot
+        let removed = scheduler.handle(1).unwrap();
+        assert_eq!(scheduler.remove(1), Ok(()));
+        assert_eq!(scheduler.add(Task::new(TaskState::Running, &mut e3)), Ok(1));
+        assert_eq!(
+            scheduler.get_by_handle(removed).err(),
+            Some(Error::NoSuchTaskId)
+        );
+
+        // a moved task's handle doesn't resolve to the task moved into its slot
+        let moved = scheduler.handle(1).unwrap();
+        let suspended = scheduler.handle(2).unwrap();
+        assert_eq!(scheduler.remove(0), Ok(()));
+        assert_eq!(scheduler.compact(), 2);
+        assert_eq!(
+            scheduler.get_by_handle(moved).err(),
+            Some(Error::NoSuchTaskId)
+        );
+        assert_eq!(
+            scheduler.remove_by_handle(suspended),
+            Err(Error::NoSuchTaskId)
+        );
+        assert_eq!(scheduler.len(), 2);
+
+        let handle = scheduler.handle(1).unwrap();
+        assert_eq!(
+            scheduler.get_by_handle(handle).map(|task| task.state),
+            Ok(TaskState::Suspended)
+        );
+    }
+
+    struct ProducerExecuter<'a> {
+        log: &'a RefCell<Log>,
+        consumer: TaskId,
+    }
+    impl Execute for ProducerExecuter<'_> {
+        fn execute(&mut self, id: TaskId) -> TaskControl {
+            self.log.borrow_mut().push(id);
+            TaskControl::Continue
+        }
+
+        fn execute_with(&mut self, id: TaskId, ctx: &mut Context) -> TaskControl {
+            ctx.yield_to(self.consumer);
+            self.execute(id)
+        }
+    }
+
+    #[test]
+    fn scheduler_yield_to() {
+        let log = RefCell::new(Log::default());
+        let mut e0 = ProducerExecuter {
+            log: &log,
+            consumer: 3,
+        };
+        let mut e1 = LogExecuter { log: &log };
+        let mut e2 = LogExecuter { log: &log };
+        let mut e3 = LogExecuter { log: &log };
+        let mut scheduler: Scheduler<4> = Scheduler::new();
+
+        scheduler
+            .add(Task::new(TaskState::Running, &mut e0))
+            .unwrap();
+        scheduler
+            .add(Task::new(TaskState::Running, &mut e1))
+            .unwrap();
+        scheduler
+            .add(Task::new(TaskState::Running, &mut e2))
+            .unwrap();
+        scheduler
+            .add(Task::new(TaskState::Running, &mut e3))
+            .unwrap();
+
+        scheduler.process();
+        scheduler.process();
+        assert_eq!(log.borrow().entries(), [0, 3, 1, 2, 0, 3, 1, 2]);
+    }
+
+    #[test]
+    fn scheduler_max_yield_depth() {
+        let log = RefCell::new(Log::default());
+        let mut e0 = ProducerExecuter {
+            log: &log,
+            consumer: 3,
+        };
+        let mut e1 = LogExecuter { log: &log };
+        let mut e2 = ProducerExecuter {
+            log: &log,
+            consumer: 1,
+        };
+        let mut e3 = ProducerExecuter {
+            log: &log,
+            consumer: 2,
+        };
+        let mut scheduler: Scheduler<4> = Scheduler::new();
+
+        scheduler
+            .add(Task::new(TaskState::Running, &mut e0))
+            .unwrap();
+        scheduler
+            .add(Task::new(TaskState::Running, &mut e1))
+            .unwrap();
+        scheduler
+            .add(Task::new(TaskState::Running, &mut e2))
+            .unwrap();
+        scheduler
+            .add(Task::new(TaskState::Running, &mut e3))
+            .unwrap();
+
+        scheduler.process();
+        assert_eq!(scheduler.peak_yield_depth(), 3);
+
+        scheduler.set_max_yield_depth(1);
+        scheduler.process();
+        assert_eq!(log.borrow().entries(), [0, 3, 2, 1, 0, 3, 1, 2]);
+        assert_eq!(scheduler.peak_yield_depth(), 3);
+    }
+
+    struct PhaseExecuter {}
+    impl Execute<u32> for PhaseExecuter {
+        fn execute(&mut self, _id: TaskId) -> TaskControl {
+            TaskControl::Continue
+        }
+
+        fn execute_with(&mut self, _id: TaskId, ctx: &mut Context<u32>) -> TaskControl {
+            *ctx.scratch().unwrap() += 1;
+            TaskControl::Continue
+        }
+    }
+
+    #[test]
+    fn scheduler_scratch() {
+        let mut e0 = PhaseExecuter {};
+        let mut e1 = PhaseExecuter {};
+        let mut scheduler: Scheduler<3, u32> = Scheduler::new();
+
+        scheduler
+            .add(Task::new(TaskState::Running, &mut e0))
+            .unwrap();
+        scheduler
+            .add(Task::new(TaskState::Running, &mut e1))
+            .unwrap();
+
+        scheduler.process();
+        scheduler.get(1).unwrap().suspend();
+        scheduler.process();
+        scheduler.process();
+
+        assert_eq!(scheduler.scratch(0), Ok(&mut 3));
+        assert_eq!(scheduler.scratch(1), Ok(&mut 1));
+        assert_eq!(scheduler.scratch(2), Err(Error::NoSuchTaskId));
+
+        assert_eq!(scheduler.remove(0), Ok(()));
+        let mut e2 = PhaseExecuter {};
+        scheduler
+            .add(Task::new(TaskState::Running, &mut e2))
+            .unwrap();
+        assert_eq!(scheduler.scratch(0), Ok(&mut 0));
+    }
+
+    #[test]
+    fn scheduler_process_grouped() {
+        let log = RefCell::new(Log::default());
+        let mut e0 = LogExecuter { log: &log };
+        let mut e1 = LogExecuter { log: &log };
+        let mut e2 = LogExecuter { log: &log };
+        let mut e3 = LogExecuter { log: &log };
+        let mut e4 = LogExecuter { log: &log };
+        let mut scheduler: Scheduler<6> = Scheduler::new();
+
+        for (executer, priority) in [
+            (&mut e0 as &mut dyn Execute, 1u8),
+            (&mut e1, 2),
+            (&mut e2, 1),
+            (&mut e3, 2),
+            (&mut e4, 0),
+        ] {
+            let mut task = Task::new(TaskState::Running, executer);
+            task.priority = priority;
+            scheduler.add(task).unwrap();
+        }
+
+        assert_eq!(scheduler.process_grouped(), 5);
+        assert_eq!(scheduler.process_grouped(), 5);
+        assert_eq!(scheduler.process_grouped(), 5);
+        assert_eq!(
+            log.borrow().entries(),
+            [1, 3, 0, 2, 4, 3, 1, 2, 0, 4, 1, 3, 0, 2, 4]
+        );
+    }
+
+    #[test]
+    fn scheduler_process_phase() {
+        let log = RefCell::new(Log::default());
+        let mut e0 = LogExecuter { log: &log };
+        let mut e1 = LogExecuter { log: &log };
+        let mut e2 = LogExecuter { log: &log };
+        let mut e3 = LogExecuter { log: &log };
+        let mut scheduler: Scheduler<4> = Scheduler::new();
+
+        for (executer, phase) in [
+            (&mut e0 as &mut dyn Execute, 2u8),
+            (&mut e1, 0),
+            (&mut e2, 1),
+            (&mut e3, 0),
+        ] {
+            let mut task = Task::new(TaskState::Running, executer);
+            task.phase = phase;
+            scheduler.add(task).unwrap();
+        }
+
+        assert_eq!(scheduler.process_phase(0), 2);
+        assert_eq!(log.borrow().entries(), [1, 3]);
+        assert_eq!(scheduler.process_phase(1), 1);
+        assert_eq!(log.borrow().entries()[2..], [2]);
+        assert_eq!(scheduler.process_phase(2), 1);
+        assert_eq!(log.borrow().entries()[3..], [0]);
+        assert_eq!(scheduler.process_phase(3), 0);
+        assert_eq!(log.borrow().entries().len(), 4);
+    }
+
+    #[test]
+    fn scheduler_enabled_mask() {
+        let log = RefCell::new(Log::default());
+        let mut e0 = LogExecuter { log: &log };
+        let mut e1 = LogExecuter { log: &log };
+        let mut e2 = LogExecuter { log: &log };
+        let mut scheduler: Scheduler<3> = Scheduler::new();
+        scheduler
+            .add(Task::new(TaskState::Running, &mut e0))
+            .unwrap();
+        scheduler
+            .add(Task::new(TaskState::Running, &mut e1))
+            .unwrap();
+        scheduler
+            .add(Task::new(TaskState::Suspended, &mut e2))
+            .unwrap();
+
+        assert_eq!(scheduler.enabled_mask(), u32::MAX);
+        scheduler.set_enabled_mask(0b110);
+        scheduler.process();
+        assert_eq!(log.borrow().entries(), [1]);
+        assert_eq!(scheduler.get(0).unwrap().state, TaskState::Running);
+        assert_eq!(scheduler.process_priority(), Some(1));
+
+        scheduler.set_enabled_mask(0b101);
+        scheduler.process();
+        assert_eq!(log.borrow().entries()[2..], [0]);
+        assert_eq!(scheduler.get(1).unwrap().state, TaskState::Running);
+        assert_eq!(scheduler.get(2).unwrap().state, TaskState::Suspended);
+
+        scheduler.set_enabled_mask(0);
+        assert_eq!(scheduler.process_priority(), None);
+        scheduler.set_enabled_mask(u32::MAX);
+        scheduler.process();
+        assert_eq!(log.borrow().entries()[3..], [0, 1]);
+    }
+
+    #[test]
+    fn scheduler_freeze() {
+        let grouped_log = RefCell::new(Log::default());
+        let frozen_log = RefCell::new(Log::default());
+        let mut grouped: [LogExecuter; 4] =
+            core::array::from_fn(|_| LogExecuter { log: &grouped_log });
+        let mut frozen: [LogExecuter; 4] =
+            core::array::from_fn(|_| LogExecuter { log: &frozen_log });
+        let mut extra = LogExecuter { log: &frozen_log };
+        let mut grouped_scheduler: Scheduler<5> = Scheduler::new();
+        let mut frozen_scheduler: Scheduler<5> = Scheduler::new();
+
+        for ((g, f), priority) in grouped
+            .iter_mut()
+            .zip(frozen.iter_mut())
+            .zip([1u8, 3, 0, 3])
+        {
+            let mut task = Task::new(TaskState::Running, g);
+            task.priority = priority;
+            grouped_scheduler.add(task).unwrap();
+            let mut task = Task::new(TaskState::Running, f);
+            task.priority = priority;
+            frozen_scheduler.add(task).unwrap();
+        }
+
+        assert!(!frozen_scheduler.is_frozen());
+        frozen_scheduler.freeze();
+        assert!(frozen_scheduler.is_frozen());
+
+        assert_eq!(grouped_scheduler.process_grouped(), 4);
+        assert_eq!(frozen_scheduler.process_frozen(), 4);
+        assert_eq!(frozen_scheduler.process_frozen(), 4);
+        assert_eq!(grouped_log.borrow().entries(), [1, 3, 0, 2]);
+        assert_eq!(frozen_log.borrow().entries(), [1, 3, 0, 2, 1, 3, 0, 2]);
+
+        let mut task = Task::new(TaskState::Running, &mut extra);
+        task.priority = 2;
+        assert_eq!(frozen_scheduler.add(task), Ok(4));
+        assert!(!frozen_scheduler.is_frozen());
+        assert_eq!(frozen_scheduler.process_frozen(), 5);
+        assert!(frozen_scheduler.is_frozen());
+        assert_eq!(frozen_log.borrow().entries()[8..], [1, 3, 4, 0, 2]);
+
+        assert_eq!(frozen_scheduler.remove(3), Ok(()));
+        assert!(!frozen_scheduler.is_frozen());
+        assert_eq!(frozen_scheduler.process_frozen(), 4);
+        assert_eq!(frozen_log.borrow().entries()[13..], [1, 4, 0, 2]);
+    }
+
+    /// Writer into a fixed buffer.
+    struct BufWriter {
+        buf: [u8; 256],
+        len: usize,
+    }
+    impl core::fmt::Write for BufWriter {
+        fn write_str(&mut self, s: &str) -> core::fmt::Result {
+            let end = self.len + s.len();
+            self.buf
+                .get_mut(self.len..end)
+                .ok_or(core::fmt::Error)?
+                .copy_from_slice(s.as_bytes());
+            self.len = end;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn scheduler_dump() {
+        let mut e0 = SomeExecuter {};
+        let mut e1 = SomeExecuter {};
+        let mut scheduler: Scheduler<3> = Scheduler::new();
+        let mut task = Task::named(TaskState::Running, "blink", &mut e0);
+        task.priority = 3;
+        scheduler.add(task).unwrap();
+        scheduler
+            .add(Task::new(TaskState::Suspended, &mut e1))
+            .unwrap();
+        scheduler.process();
+        scheduler.process();
+
+        let mut out = BufWriter {
+            buf: [0; 256],
+            len: 0,
+        };
+        assert_eq!(scheduler.dump(&mut out), Ok(()));
+
+        let mut lines = core::str::from_utf8(&out.buf[..out.len]).unwrap().lines();
+        assert_eq!(
+            lines.next(),
+            Some(" ID NAME             STATE     PRIO       RUNS")
+        );
+        assert_eq!(
+            lines.next(),
+            Some("  0 blink            Running      3          2")
+        );
+        assert_eq!(
+            lines.next(),
+            Some("  1 -                Suspended    0          0")
+        );
+        assert_eq!(lines.next(), None);
+
+        let mut small = BufWriter {
+            buf: [0; 256],
+            len: 250,
+        };
+        assert_eq!(scheduler.dump(&mut small), Err(core::fmt::Error));
+    }
+
+    /// Fails with error code 7 while `healthy` is false.
+    struct HealthExecuter<'a> {
+        healthy: &'a core::cell::Cell<bool>,
+    }
+    impl Execute for HealthExecuter<'_> {
+        fn execute(&mut self, _id: TaskId) -> TaskControl {
+            if self.healthy.get() {
+                TaskControl::Continue
+            } else {
+                TaskControl::Error(7)
+            }
+        }
+    }
+
+    #[test]
+    fn scheduler_last_error() {
+        let healthy = core::cell::Cell::new(false);
+        let mut failing = HealthExecuter { healthy: &healthy };
+        let mut other = SomeExecuter {};
+        let mut scheduler: Scheduler<2> = Scheduler::new();
+        scheduler
+            .add(Task::new(TaskState::Running, &mut failing))
+            .unwrap();
+        scheduler
+            .add(Task::new(TaskState::Running, &mut other))
+            .unwrap();
+
+        scheduler.process();
+        assert_eq!(scheduler.get(0).unwrap().state, TaskState::Faulted);
+        assert_eq!(scheduler.get(0).unwrap().last_error(), Some(7));
+        assert_eq!(scheduler.get(1).unwrap().last_error(), None);
+
+        healthy.set(true);
+        scheduler.get(0).unwrap().resume();
+        scheduler.process();
+        assert_eq!(scheduler.get(0).unwrap().state, TaskState::Running);
+        assert_eq!(scheduler.get(0).unwrap().last_error(), Some(7));
+
+        scheduler.clear_errors();
+        assert_eq!(scheduler.get(0).unwrap().last_error(), None);
+    }
+
+    #[test]
+    fn scheduler_wait_for_runs() {
+        let log = RefCell::new(Log::default());
+        let mut producer = LogExecuter { log: &log };
+        let mut consumer = LogExecuter { log: &log };
+        let mut scheduler: Scheduler<2> = Scheduler::new();
+
+        scheduler
+            .add(Task::new(TaskState::Running, &mut producer))
+            .unwrap();
+        scheduler
+            .add(Task::new(TaskState::Running, &mut consumer))
+            .unwrap();
+        assert_eq!(scheduler.wait_for_runs(1, 0, 3), Ok(()));
+        assert_eq!(
+            scheduler.wait_for_runs(1, 2, 3),
+            Err(Error::InvalidParameter)
+        );
+
+        for _ in 0..6 {
+            scheduler.process();
+        }
+        assert_eq!(log.borrow().entries(), [0, 0, 0, 1, 0, 0, 0, 1]);
+        assert_eq!(scheduler.runs(0), Ok(6));
+        assert_eq!(scheduler.runs(1), Ok(2));
+
+        assert_eq!(scheduler.wait_for_runs(1, 0, 0), Ok(()));
+        scheduler.process();
+        assert_eq!(log.borrow().entries()[8..], [0, 1]);
+    }
+
+    #[test]
+    fn scheduler_wait_for_runs_priority() {
+        let log = RefCell::new(Log::default());
+        let mut producer = LogExecuter { log: &log };
+        let mut consumer = LogExecuter { log: &log };
+        let mut scheduler: Scheduler<2> = Scheduler::new();
+
+        scheduler
+            .add(Task::new(TaskState::Running, &mut producer))
+            .unwrap();
+        let mut task = Task::new(TaskState::Running, &mut consumer);
+        task.priority = 5;
+        scheduler.add(task).unwrap();
+        assert_eq!(scheduler.wait_for_runs(1, 0, 1), Ok(()));
+
+        assert_eq!(scheduler.process_priority(), Some(0));
+        assert_eq!(scheduler.process_priority(), Some(1));
+        assert_eq!(scheduler.process_priority(), Some(0));
+        assert_eq!(log.borrow().entries(), [0, 1, 0]);
+
+        scheduler.set_random_seed(42);
+        for _ in 0..8 {
+            scheduler.process_random();
+        }
+        assert!(log.borrow().entries().windows(2).all(|w| w != [1, 1]));
+    }
+
+    #[test]
+    fn scheduler_wait_for_runs_compact() {
+        let log = RefCell::new(Log::default());
+        let mut filler: SomeExecuter = SomeExecuter {};
+        let mut producer = LogExecuter { log: &log };
+        let mut consumer = LogExecuter { log: &log };
+        let mut scheduler: Scheduler<3> = Scheduler::new();
+
+        scheduler
+            .add(Task::new(TaskState::Running, &mut filler))
+            .unwrap();
+        scheduler
+            .add(Task::new(TaskState::Running, &mut producer))
+            .unwrap();
+        scheduler
+            .add(Task::new(TaskState::Running, &mut consumer))
+            .unwrap();
+        assert_eq!(scheduler.wait_for_runs(2, 1, 2), Ok(()));
+        scheduler.process();
+        assert_eq!(scheduler.remove(0), Ok(()));
+        assert_eq!(scheduler.compact(), 2);
+        assert_eq!(scheduler.runs(0), Ok(1));
+
+        for _ in 0..3 {
+            scheduler.process();
+        }
+        assert_eq!(log.borrow().entries(), [1, 0, 1, 0, 0, 1]);
+    }
+
+    struct SleepyExecuter<'a> {
+        log: &'a RefCell<Log>,
+    }
+    impl Execute for SleepyExecuter<'_> {
+        fn execute(&mut self, id: TaskId) -> TaskControl {
+            self.log.borrow_mut().push(id);
+            TaskControl::SleepCycles(3)
+        }
+    }
+
+    #[test]
+    fn scheduler_sleep_cycles() {
+        let log = RefCell::new(Log::default());
+        let mut e0 = LogExecuter { log: &log };
+        let mut e1 = SleepyExecuter { log: &log };
+        let mut scheduler: Scheduler<2> = Scheduler::new();
+
+        scheduler
+            .add(Task::new(TaskState::Running, &mut e0))
+            .unwrap();
+        scheduler
+            .add(Task::new(TaskState::Running, &mut e1))
+            .unwrap();
+
+        for _ in 0..6 {
+            scheduler.process();
+        }
+        assert_eq!(log.borrow().entries(), [0, 1, 0, 0, 0, 0, 1, 0]);
+        assert_eq!(scheduler.get(1).unwrap().state, TaskState::Delayed);
+    }
 }
@@ -0,0 +1,104 @@
+// ************************************************************************************************
+// DESCRIPTION
+// ************************************************************************************************
+
+//! # sim.rs
+//!
+//! Host simulation helpers (requires the `std` feature).
+//!
+//! * A `TickThread` emulates a hardware timer interrupt by calling
+//!   update() on a soft timer pool from a background thread at a fixed
+//!   interval.
+//! * The pool is shared as `Arc<Mutex<SofTimers>>` because `SofTimers`
+//!   itself is not `Sync`.
+//! * The thread stops when the `TickThread` is dropped.
+//!
+
+// ************************************************************************************************
+// USES
+// ************************************************************************************************
+
+use crate::softtimer::SofTimers;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+// ************************************************************************************************
+// TYPES AND STRUCTURES
+// ************************************************************************************************
+
+/// Background thread ticking a soft timer pool.
+pub struct TickThread {
+    running: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+// ************************************************************************************************
+// IMPLEMENTATIONS
+// ************************************************************************************************
+
+impl TickThread {
+    /// Spawns a thread calling update() on `timers` every `period`.
+    ///
+    pub fn spawn(timers: Arc<Mutex<SofTimers<'static>>>, period: Duration) -> Self {
+        let running = Arc::new(AtomicBool::new(true));
+        let thread_running = running.clone();
+
+        let thread = thread::spawn(move || {
+            while thread_running.load(Ordering::Relaxed) {
+                thread::sleep(period);
+                timers.lock().unwrap().update();
+            }
+        });
+
+        TickThread {
+            running,
+            thread: Some(thread),
+        }
+    }
+
+    /// Stops the tick thread and waits for it to terminate.
+    ///
+    pub fn stop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for TickThread {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+// ************************************************************************************************
+// TESTS
+// ************************************************************************************************
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    #[test]
+    fn sim_tick_thread_signals() {
+        let timers = Arc::new(Mutex::new(SofTimers::new()));
+        let h = timers.lock().unwrap().create().unwrap();
+        assert_eq!(timers.lock().unwrap().start(h, 5, false), Ok(()));
+
+        let start = Instant::now();
+        let mut ticker = TickThread::spawn(timers.clone(), Duration::from_millis(2));
+
+        while !timers.lock().unwrap().is_signaled(h).unwrap() {
+            assert!(start.elapsed() < Duration::from_secs(2));
+            thread::sleep(Duration::from_millis(1));
+        }
+        ticker.stop();
+
+        assert!(start.elapsed() >= Duration::from_millis(10));
+    }
+}
@@ -27,14 +27,69 @@
 // ************************************************************************************************
 
 use crate::{Signal, SignalState};
-use core::borrow::Borrow;
-use core::cell::{Ref, RefCell};
-use core::sync::atomic::{AtomicUsize, Ordering};
+use core::cell::{Cell, Ref, RefCell, RefMut};
+use core::fmt::Debug;
+use core::ops::{Add, Div, Mul, Range, Rem, Sub};
+use core::sync::atomic::Ordering;
 
 // ************************************************************************************************
 // TRAITS
 // ************************************************************************************************
 
+/// Unsigned integer type of the timer counters and the tick of a pool, see
+/// SofTimers::with_counter(). A narrow type saves memory and atomic
+/// operations on small targets, a wide one allows long timeouts. It is
+/// implemented for `u16`, `u32`, `u64` and `usize` as far as the target
+/// supports atomics of that width.
+pub trait TimerCounter:
+    Copy
+    + Debug
+    + Default
+    + Ord
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Rem<Output = Self>
+{
+    /// Atomic type storing a counter.
+    type Atomic: AtomicCounter<Self>;
+
+    const ZERO: Self;
+    const ONE: Self;
+
+    // Arithmetic like the inherent methods of the integer types.
+    fn saturating_add(self, rhs: Self) -> Self;
+    fn saturating_sub(self, rhs: Self) -> Self;
+    fn saturating_mul(self, rhs: Self) -> Self;
+    fn wrapping_add(self, rhs: Self) -> Self;
+    fn wrapping_sub(self, rhs: Self) -> Self;
+    fn checked_add(self, rhs: Self) -> Option<Self>;
+    fn checked_div(self, rhs: Self) -> Option<Self>;
+    fn checked_rem(self, rhs: Self) -> Option<Self>;
+
+    /// Converts the value into u64 without loss.
+    fn to_u64(self) -> u64;
+
+    /// Converts a u64 value, fails if it doesn't fit.
+    fn from_u64(value: u64) -> Option<Self>;
+}
+
+/// Atomic storage of a TimerCounter value.
+pub trait AtomicCounter<T>: Debug {
+    fn new(value: T) -> Self;
+    fn load(&self, order: Ordering) -> T;
+    fn store(&self, value: T, order: Ordering);
+    fn fetch_sub(&self, value: T, order: Ordering) -> T;
+}
+
+/// Timer pool of any counter width as seen by a task waiting for one of
+/// its timers, see Task::wait_timer().
+pub trait TimerSignals {
+    /// Checks if a timer is signaled, see SofTimers::is_signaled().
+    fn is_signaled(&self, handle: SoftTimerHandle) -> Result<bool, SoftTimerErr>;
+}
+
 // ************************************************************************************************
 // TYPES AND STRUCTURES
 // ************************************************************************************************
@@ -54,49 +109,144 @@ pub enum SoftTimerErr {
     LimitExceeded,
     NoSuchTimer,
     InvalidParameter,
+    AlreadyRunning,
+    StaleHandle,
 }
 
 type Counter = usize;
 type SoftTimerHandle = usize;
+type Generation = u8;
+type Slots<C> = [Option<RefCell<SoftTimerData<C>>>; MAX_SOFT_COUNTER];
 
 /// SoftTimerData instance
 #[derive(Debug)]
 
-pub struct SoftTimerData {
+pub struct SoftTimerData<C: TimerCounter = Counter> {
     state: State,
-    counter: AtomicUsize,
-    threshold: Counter,
+    counter: C::Atomic,
+    threshold: C,
     auto_restart: bool,
+    grace: C,
+    grace_left: C,
+    expired_at: Option<C>,
+    chained: Option<SoftTimerHandle>,
+    warn_at: C,
+    realign: Cell<bool>,
+    overrun: Cell<C>,
+    tag: Option<u16>,
+    unit: C,
+    unit_ticks: Cell<C>,
+    min_interval: C,
+    last_callback: Option<C>,
+    suppressed: C,
+    restart_gate: Option<fn(SoftTimerHandle) -> bool>,
+    latched: bool,
+}
+
+/// Copy of the externally visible timer data for comparisons.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Snapshot<C = Counter> {
+    pub handle: SoftTimerHandle,
+    pub state: State,
+    pub counter: C,
+    pub threshold: C,
+}
+
+/// System action of a pool, see SofTimers::set_expiry_action().
+pub type ExpiryAction<'a> = &'a mut (dyn FnMut(SoftTimerHandle) + Send);
+
+/// One shot timer of a pool used as timeout, see SofTimers::timeout().
+pub struct Timeout<'t, 'a, C: TimerCounter = Counter> {
+    timers: &'t SofTimers<'a, C>,
+    handle: SoftTimerHandle,
+}
+
+/// Iterator over the remaining ticks of all timers, see
+/// SofTimers::iter_remaining().
+struct RemainingIter<'t, 'a, C: TimerCounter> {
+    timers: &'t SofTimers<'a, C>,
+    slots: Ref<'t, Slots<C>>,
+    index: usize,
+}
+
+/// Timers expired by one update, see SofTimers::update_with().
+struct Expired {
+    handles: [SoftTimerHandle; MAX_SOFT_COUNTER],
+    one_shot: [bool; MAX_SOFT_COUNTER],
+    reported: [bool; MAX_SOFT_COUNTER],
+    count: usize,
+}
+
+/// Binary min-heap of the next event tick of each timer used by
+/// SofTimers::advance_to(). The position of each timer is kept for moving
+/// or removing its entry, equal ticks are ordered by slot index.
+struct ExpiryHeap<C> {
+    items: [(C, usize); MAX_SOFT_COUNTER],
+    position: [Option<usize>; MAX_SOFT_COUNTER],
+    len: usize,
 }
 
 // ************************************************************************************************
 // CONSTANTS
 // ************************************************************************************************
 
-const MAX_SOFT_COUNTER: usize = 16usize;
+/// Number of timers in a SofTimers pool.
+pub const MAX_SOFT_COUNTER: usize = 16usize;
+
+/// Size of a timer record in the serialized representation: slot index,
+/// state, flags, tag as u16 and threshold, counter, grace, warn_at, unit,
+/// unit ticks and min_interval as u32, all little endian.
+const RECORD_SIZE: usize = 33;
+
+/// Flags of a serialized timer record.
+const RECORD_AUTO_RESTART: u8 = 0x01;
+const RECORD_TAGGED: u8 = 0x02;
+
+/// A handle carries the slot index in its lower bits and the generation
+/// of the slot above them.
+const HANDLE_INDEX_BITS: u32 = 8;
+const HANDLE_INDEX_MASK: SoftTimerHandle = (1 << HANDLE_INDEX_BITS) - 1;
 
 // ************************************************************************************************
 // LOCAL VARIABLES
 // ************************************************************************************************
 
-pub struct SofTimers {
-    timer: RefCell<[Option<RefCell<SoftTimerData>>; MAX_SOFT_COUNTER]>,
+pub struct SofTimers<'a, C: TimerCounter = Counter> {
+    timer: RefCell<Slots<C>>,
+    coalesce_window: Cell<C>,
+    ticks: Cell<C>,
+    uptime: Cell<u64>,
+    generations: [Cell<Generation>; MAX_SOFT_COUNTER],
+    expiry_action: Cell<Option<ExpiryAction<'a>>>,
+    frozen: Cell<bool>,
+    peak_running: Cell<usize>,
+    synced: [Cell<C>; MAX_SOFT_COUNTER],
+    lagging: Cell<bool>,
 }
 
 // ************************************************************************************************
 // IMPLEMENTATIONS
 // ************************************************************************************************
 
-impl Signal for SoftTimerData {
+impl<C: TimerCounter> Signal for SoftTimerData<C> {
     /// Checks if the timer timed out or not. If the timer timed out, the signal
     /// will be true, otherwise false.
     ///
     fn get_signal_state(&self) -> SignalState {
         let counter = self.counter.load(Ordering::Relaxed);
 
-        if (State::Running == self.state) && (0 == counter) {
-            if self.auto_restart {
-                self.counter.store(self.threshold, Ordering::Relaxed);
+        if (State::Running == self.state) && (C::ZERO == counter) {
+            if self.auto_restart && (C::ZERO == self.grace) {
+                let overrun = self.overrun.take();
+                let phase = match self.realign.take() {
+                    true => overrun
+                        .checked_rem(self.threshold.saturating_mul(self.unit))
+                        .unwrap_or(C::ZERO),
+                    false => C::ZERO,
+                };
+                self.counter
+                    .store(self.threshold - phase / self.unit, Ordering::Relaxed);
+                self.unit_ticks.set(phase % self.unit);
             }
             SignalState::Signaled
         } else {
@@ -105,272 +255,3231 @@ impl Signal for SoftTimerData {
     }
 }
 
-impl SofTimers {
-    const TIMER_INIT_NONE: Option<RefCell<SoftTimerData>> = None;
+impl<C: TimerCounter> SoftTimerData<C> {
+    /// Initializes the data of a disabled timer.
+    ///
+    fn new() -> Self {
+        SoftTimerData {
+            state: State::Disabled,
+            counter: AtomicCounter::new(C::ZERO),
+            threshold: C::ZERO,
+            auto_restart: false,
+            grace: C::ZERO,
+            grace_left: C::ZERO,
+            expired_at: None,
+            chained: None,
+            warn_at: C::ZERO,
+            realign: Cell::new(false),
+            overrun: Cell::new(C::ZERO),
+            tag: None,
+            unit: C::ONE,
+            unit_ticks: Cell::new(C::ZERO),
+            min_interval: C::ZERO,
+            last_callback: None,
+            suppressed: C::ZERO,
+            restart_gate: None,
+            latched: false,
+        }
+    }
+
+    /// Checks the invariants of the timer data in debug builds:
+    /// * the counter never exceeds the threshold
+    /// * a disabled timer has a zero counter
+    /// * the remaining grace period never exceeds the grace period
+    /// * the ticks counted for the current unit stay below the unit
+    ///
+    fn check_invariants(&self) {
+        let counter = self.counter.load(Ordering::Relaxed);
+
+        debug_assert!(counter <= self.threshold, "counter exceeds threshold");
+        debug_assert!(
+            (State::Disabled != self.state) || (C::ZERO == counter),
+            "disabled timer with non-zero counter"
+        );
+        debug_assert!(self.grace_left <= self.grace, "grace left exceeds grace");
+        debug_assert!(self.unit_ticks.get() < self.unit, "unit ticks exceed unit");
+    }
+
+    /// Forces the counter to zero, i.e. into the signaled condition.
+    ///
+    fn expire(&mut self) {
+        self.counter.store(C::ZERO, Ordering::Relaxed);
+        self.grace_left = self.grace;
+    }
+
+    /// Checks if the timer is an expired auto restart timer without grace
+    /// period, which waits for being restarted by reading its signal.
+    ///
+    fn is_overrunning(&self) -> bool {
+        (C::ZERO == self.counter.load(Ordering::Relaxed))
+            && self.auto_restart
+            && (C::ZERO == self.grace)
+    }
+
+    /// Takes the number of expiries of a running timer, see
+    /// SofTimers::check_and_count_expiries().
+    ///
+    fn take_expiries(&mut self) -> usize {
+        if (State::Running != self.state) || (C::ZERO != self.counter.load(Ordering::Relaxed)) {
+            return 0;
+        }
+        if !self.is_overrunning() {
+            return 1;
+        }
+
+        let period = self.threshold.saturating_mul(self.unit);
+        let overrun = self.overrun.take();
+        let phase = overrun.checked_rem(period).unwrap_or(C::ZERO);
+
+        self.realign.set(false);
+        self.counter
+            .store(self.threshold - phase / self.unit, Ordering::Relaxed);
+        self.unit_ticks.set(phase % self.unit);
+
+        let expiries = C::ONE.saturating_add(overrun.checked_div(period).unwrap_or(C::ZERO));
+        usize::try_from(expiries.to_u64()).unwrap_or(usize::MAX)
+    }
+
+    /// Checks if an expiry at tick `now` gets reported to the callbacks,
+    /// see SofTimers::set_min_interval(). Counts it as suppressed
+    /// otherwise.
+    ///
+    fn allow_callback(&mut self, now: C) -> bool {
+        if self
+            .last_callback
+            .is_some_and(|last| now.wrapping_sub(last) < self.min_interval)
+        {
+            self.suppressed = self.suppressed.saturating_add(C::ONE);
+            return false;
+        }
+
+        self.last_callback = Some(now);
+        true
+    }
+
+    /// Gets the number of ticks elapsed since the timer was started or
+    /// last restarted, including the ticks an expired auto restart timer
+    /// waits for being restarted.
+    ///
+    fn elapsed(&self) -> C {
+        let units = self.threshold - self.counter.load(Ordering::Relaxed);
+
+        units
+            .saturating_mul(self.unit)
+            .saturating_add(self.unit_ticks.get())
+            .saturating_add(self.overrun.get())
+    }
+
+    /// Gets the number of ticks until `units` more units of the timer
+    /// elapsed.
+    ///
+    fn ticks_until(&self, units: C) -> C {
+        units
+            .saturating_mul(self.unit)
+            .saturating_sub(self.unit_ticks.get())
+    }
+
+    /// Gets the number of ticks until the next event of a running timer,
+    /// i.e. its expiry or the end of its grace period, see
+    /// SofTimers::next_wakeup().
+    ///
+    fn next_event(&self) -> Option<C> {
+        let counter = self.counter.load(Ordering::Relaxed);
+
+        match (self.state, C::ZERO == counter) {
+            (State::Running, true) if C::ZERO < self.grace => {
+                Some(self.ticks_until(self.grace_left))
+            }
+            (State::Running, false) => Some(self.ticks_until(counter)),
+            _ => None,
+        }
+    }
+
+    /// Gets the tick of the next event after tick `tick`, see
+    /// SofTimers::advance_to(). An event due at `tick` already (the end of
+    /// an empty grace period) is checked on every following tick.
+    ///
+    fn event_tick(&self, tick: C) -> Option<C> {
+        self.next_event()
+            .map(|ticks| tick.saturating_add(ticks.max(C::ONE)))
+    }
+
+    /// Counts down a running timer by `ticks` ticks like tick(), which
+    /// must not expire the timer or end its grace period meanwhile, see
+    /// SofTimers::advance_to().
+    ///
+    fn skip(&mut self, ticks: C) {
+        if State::Running != self.state {
+            return;
+        }
+
+        if self.is_overrunning() {
+            self.overrun.set(self.overrun.get().saturating_add(ticks));
+        }
+
+        let ticks = self.unit_ticks.get().saturating_add(ticks);
+        let units = ticks / self.unit;
+        self.unit_ticks.set(ticks % self.unit);
+
+        let counter = self.counter.load(Ordering::Relaxed);
+        if C::ZERO < counter {
+            self.counter.store(counter - units, Ordering::Relaxed);
+        } else if C::ZERO < self.grace {
+            self.grace_left = self.grace_left - units;
+        }
+    }
+
+    /// Counts down a running timer by one tick. Returns true if the
+    /// timer expired by this tick.
+    ///
+    fn tick(&mut self) -> bool {
+        if State::Running != self.state {
+            return false;
+        }
+
+        if self.is_overrunning() {
+            self.overrun.set(self.overrun.get().saturating_add(C::ONE));
+        }
+
+        if C::ONE < self.unit {
+            let ticks = self.unit_ticks.get() + C::ONE;
+            if ticks < self.unit {
+                self.unit_ticks.set(ticks);
+                return false;
+            }
+            self.unit_ticks.set(C::ZERO);
+        }
+
+        let counter = self.counter.load(Ordering::Relaxed);
+        if C::ZERO < counter {
+            self.counter.fetch_sub(C::ONE, Ordering::Relaxed);
+
+            if C::ONE == counter {
+                self.expire();
+                return true;
+            }
+        } else if C::ZERO < self.grace {
+            self.grace_left = self.grace_left.saturating_sub(C::ONE);
+
+            if C::ZERO == self.grace_left {
+                if self.auto_restart {
+                    self.counter.store(self.threshold, Ordering::Relaxed);
+                } else {
+                    self.state = State::Disabled;
+                }
+            }
+        }
+
+        false
+    }
+}
 
+impl SofTimers<'_> {
     pub fn new() -> Self {
+        Self::with_counter()
+    }
+}
+
+impl<'a, C: TimerCounter> SofTimers<'a, C> {
+    const TIMER_INIT_NONE: Option<RefCell<SoftTimerData<C>>> = None;
+
+    /// Creates a pool with counters of type `C`, which limits the longest
+    /// timeout and the wrap around of the tick, see now(). new() creates
+    /// a pool with `usize` counters.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lwos::softtimer::SofTimers;
+    ///
+    /// let timers: SofTimers<u16> = SofTimers::with_counter();
+    /// let handle = timers.create().unwrap();
+    /// assert!(timers.start(handle, 60_000, false).is_ok());
+    /// ```
+    pub fn with_counter() -> Self {
         SofTimers {
             timer: RefCell::new([Self::TIMER_INIT_NONE; MAX_SOFT_COUNTER]),
+            coalesce_window: Cell::new(C::ZERO),
+            ticks: Cell::new(C::ZERO),
+            uptime: Cell::new(0),
+            generations: [const { Cell::new(0) }; MAX_SOFT_COUNTER],
+            expiry_action: Cell::new(None),
+            frozen: Cell::new(false),
+            peak_running: Cell::new(0),
+            synced: [const { Cell::new(C::ZERO) }; MAX_SOFT_COUNTER],
+            lagging: Cell::new(false),
         }
     }
 
     /// Create a new SofTimer
     ///
     pub fn create(&self) -> Result<SoftTimerHandle, SoftTimerErr> {
-        let mut timers = self.timer.borrow_mut();
+        let mut timers = self.slots_mut();
         match timers.iter().position(|x| x.is_none()) {
             Some(id) => {
-                timers[id] = Some(RefCell::new(SoftTimerData {
-                    state: State::Disabled,
-                    counter: AtomicUsize::new(0),
-                    threshold: 0,
-                    auto_restart: false,
-                }));
+                timers[id] = Some(RefCell::new(SoftTimerData::new()));
 
-                Ok(id)
+                Ok(self.handle_of(id))
             }
             None => Err(SoftTimerErr::LimitExceeded),
         }
     }
 
-    pub fn delete(&self, handle: SoftTimerHandle) -> Result<(), SoftTimerErr> {
-        if handle < MAX_SOFT_COUNTER {
-            let mut timers = self.timer.borrow_mut();
-            if let Some(_t) = timers[handle].borrow() {
-                timers[handle] = None;
+    /// Creates a block of `n` disabled timers with consecutive handles.
+    /// The timers cost nothing in update() until they get started.
+    /// All slots of the block get the same generation to keep the
+    /// handles consecutive.
+    ///
+    pub fn reserve(&self, n: usize) -> Result<Range<SoftTimerHandle>, SoftTimerErr> {
+        if (0 == n) || (MAX_SOFT_COUNTER < n) {
+            return Err(SoftTimerErr::InvalidParameter);
+        }
+
+        let mut timers = self.slots_mut();
+        match (0..=(MAX_SOFT_COUNTER - n))
+            .find(|&first| timers[first..first + n].iter().all(|x| x.is_none()))
+        {
+            Some(first) => {
+                let slots = first..first + n;
+                let generation = self.generations[slots.clone()]
+                    .iter()
+                    .map(Cell::get)
+                    .max()
+                    .unwrap_or(0);
+
+                for (entry, slot_generation) in timers[slots.clone()]
+                    .iter_mut()
+                    .zip(self.generations[slots].iter())
+                {
+                    *entry = Some(RefCell::new(SoftTimerData::new()));
+                    slot_generation.set(generation);
+                }
 
-                return Ok(());
+                let handle = self.handle_of(first);
+                Ok(handle..handle + n)
             }
+            None => Err(SoftTimerErr::LimitExceeded),
         }
+    }
+
+    /// Deletes a timer. The slot gets a new generation, so the handle
+    /// and all copies of it become invalid.
+    ///
+    pub fn delete(&self, handle: SoftTimerHandle) -> Result<(), SoftTimerErr> {
+        let index = self.index_of(handle).map_err(|err| match err {
+            SoftTimerErr::InvalidParameter => SoftTimerErr::NoSuchTimer,
+            err => err,
+        })?;
 
-        Err(SoftTimerErr::NoSuchTimer)
+        self.slots_mut()[index] = None;
+        let generation = &self.generations[index];
+        generation.set(generation.get().wrapping_add(1));
+
+        Ok(())
     }
 
     /// Starts a timer. If the given threshold timer is timed out, the timer
     /// will signal. If the timer is already running, it will be restarted
-    /// with the new given threshold
+    /// with the new given threshold. Use try_start() to reject starting an
+    /// already running timer instead.
     ///
     pub fn start(
         &self,
         handle: SoftTimerHandle,
-        threshold: Counter,
+        threshold: C,
         auto_restart: bool,
     ) -> Result<(), SoftTimerErr> {
-        if handle < MAX_SOFT_COUNTER {
-            let timers: Ref<'_, [Option<RefCell<SoftTimerData>>; 16]> = self.timer.borrow();
+        self.with_timer(handle, |data| {
+            data.threshold = threshold;
+            data.counter.store(threshold, Ordering::Relaxed);
+            data.auto_restart = auto_restart;
+            data.grace = C::ZERO;
+            data.grace_left = C::ZERO;
+            data.expired_at = None;
+            data.realign.set(false);
+            data.overrun.set(C::ZERO);
+            data.unit_ticks.set(C::ZERO);
+            data.state = State::Running;
+        })?;
+        self.record_peak();
+
+        Ok(())
+    }
 
-            if let Some(t) = &timers[handle] {
-                let mut data = t.borrow_mut();
-                data.threshold = threshold;
-                data.counter.store(threshold, Ordering::Relaxed);
-                data.auto_restart = auto_restart;
-                data.state = State::Running;
+    /// Starts a timer with a new threshold like start() and returns the
+    /// previous threshold, e.g. to log a rate change.
+    ///
+    pub fn reconfigure(
+        &self,
+        handle: SoftTimerHandle,
+        threshold: C,
+        auto_restart: bool,
+    ) -> Result<C, SoftTimerErr> {
+        let previous = self.with_timer(handle, |data| data.threshold)?;
+        self.start(handle, threshold, auto_restart)?;
 
-                return Ok(());
-            }
-        } else {
+        Ok(previous)
+    }
+
+    /// Starts a timer like start(), but the first expiry happens on the
+    /// next update(). Further expiries of an auto restart timer follow
+    /// every `threshold` updates.
+    ///
+    pub fn start_immediate(
+        &self,
+        handle: SoftTimerHandle,
+        threshold: C,
+        auto_restart: bool,
+    ) -> Result<(), SoftTimerErr> {
+        self.start(handle, threshold, auto_restart)?;
+        self.with_timer(handle, |data| {
+            data.counter.store(threshold.min(C::ONE), Ordering::Relaxed);
+        })
+    }
+
+    /// Starts a timer like start() with a threshold given in milliseconds
+    /// for an update() rate of `tick_hz`. The threshold is rounded up to
+    /// full ticks. Fails with `SoftTimerErr::LimitExceeded` if the
+    /// threshold in ticks doesn't fit into the counter.
+    ///
+    pub fn start_millis(
+        &self,
+        handle: SoftTimerHandle,
+        ms: C,
+        tick_hz: C,
+        auto_restart: bool,
+    ) -> Result<(), SoftTimerErr> {
+        if C::ZERO == tick_hz {
             return Err(SoftTimerErr::InvalidParameter);
         }
 
-        Err(SoftTimerErr::NoSuchTimer)
+        let threshold = ms
+            .to_u64()
+            .checked_mul(tick_hz.to_u64())
+            .and_then(|product| C::from_u64(product.div_ceil(1000)))
+            .ok_or(SoftTimerErr::LimitExceeded)?;
+
+        self.start(handle, threshold, auto_restart)
     }
 
-    /// Restarts a timer
+    /// Starts a timer like start() with a grace period. Once expired, the
+    /// timer stays signaled for `grace` ticks. Afterwards it is restarted
+    /// if `auto_restart` is set, otherwise it gets disabled. Reading the
+    /// signal does not restart a timer with a grace period.
     ///
-    pub fn restart(&self, handle: SoftTimerHandle) -> Result<(), SoftTimerErr> {
-        if handle < MAX_SOFT_COUNTER {
-            let timers: Ref<'_, [Option<RefCell<SoftTimerData>>; 16]> = self.timer.borrow();
+    pub fn start_with_grace(
+        &self,
+        handle: SoftTimerHandle,
+        threshold: C,
+        auto_restart: bool,
+        grace: C,
+    ) -> Result<(), SoftTimerErr> {
+        self.start(handle, threshold, auto_restart)?;
+        self.with_timer(handle, |data| data.grace = grace)
+    }
 
-            if let Some(t) = &timers[handle] {
-                let mut data = t.borrow_mut();
-                data.counter.store(data.threshold, Ordering::Relaxed);
-                data.state = State::Running;
+    /// Starts a timer like start() and tags it with a user value, so
+    /// find_by_tag() locates it without storing the handle. The tag is
+    /// kept until the timer gets deleted or tagged again.
+    ///
+    pub fn start_tagged(
+        &self,
+        handle: SoftTimerHandle,
+        threshold: C,
+        auto_restart: bool,
+        tag: u16,
+    ) -> Result<(), SoftTimerErr> {
+        self.start(handle, threshold, auto_restart)?;
+        self.with_timer(handle, |data| data.tag = Some(tag))
+    }
 
-                return Ok(());
-            }
-        } else {
-            return Err(SoftTimerErr::InvalidParameter);
+    /// Starts a timer like start(), but fails with
+    /// `SoftTimerErr::AlreadyRunning` if the timer is running already.
+    /// The running timer is left unchanged in that case.
+    ///
+    pub fn try_start(
+        &self,
+        handle: SoftTimerHandle,
+        threshold: C,
+        auto_restart: bool,
+    ) -> Result<(), SoftTimerErr> {
+        if State::Running == self.with_timer(handle, |data| data.state)? {
+            return Err(SoftTimerErr::AlreadyRunning);
         }
 
-        Err(SoftTimerErr::NoSuchTimer)
+        self.start(handle, threshold, auto_restart)
+    }
+
+    /// Sets the counter of a timer without changing its state or threshold,
+    /// e.g. to align it to an external event. Fails with
+    /// `SoftTimerErr::InvalidParameter` if the value exceeds the threshold
+    /// or is not zero for a disabled timer.
+    ///
+    pub fn set_counter(&self, handle: SoftTimerHandle, value: C) -> Result<(), SoftTimerErr> {
+        self.with_timer(handle, |data| {
+            if (value > data.threshold) || ((State::Disabled == data.state) && (C::ZERO < value)) {
+                return Err(SoftTimerErr::InvalidParameter);
+            }
+
+            data.counter.store(value, Ordering::Relaxed);
+            Ok(())
+        })?
+    }
+
+    /// Restarts a timer
+    ///
+    pub fn restart(&self, handle: SoftTimerHandle) -> Result<(), SoftTimerErr> {
+        self.with_timer(handle, |data| {
+            data.counter.store(data.threshold, Ordering::Relaxed);
+            data.expired_at = None;
+            data.realign.set(false);
+            data.overrun.set(C::ZERO);
+            data.unit_ticks.set(C::ZERO);
+            data.state = State::Running;
+        })?;
+        self.record_peak();
+
+        Ok(())
+    }
+
+    /// Restarts a timer like restart(), but keeps its phase aligned to the
+    /// pool tick (see now()), so it expires when the tick is a multiple
+    /// of the threshold. Timers with the same period restarted at
+    /// different times expire in the same update.
+    ///
+    pub fn restart_aligned(&self, handle: SoftTimerHandle) -> Result<(), SoftTimerErr> {
+        let ticks = self.ticks.get();
+
+        self.with_timer(handle, |data| {
+            let period = data.threshold.saturating_mul(data.unit);
+            let phase = ticks.checked_rem(period).unwrap_or(C::ZERO);
+            data.counter
+                .store(data.threshold - phase / data.unit, Ordering::Relaxed);
+            data.unit_ticks.set(phase % data.unit);
+            data.expired_at = None;
+            data.realign.set(false);
+            data.overrun.set(C::ZERO);
+            data.state = State::Running;
+        })?;
+        self.record_peak();
+
+        Ok(())
     }
 
     /// Stops a timer. Note, in stop state the timer will not signal.
     ///
     pub fn stop(&self, handle: SoftTimerHandle) -> Result<(), SoftTimerErr> {
-        if handle < MAX_SOFT_COUNTER {
-            let timers: Ref<'_, [Option<RefCell<SoftTimerData>>; 16]> = self.timer.borrow();
+        self.with_timer(handle, |data| data.state = State::Stopped)
+    }
 
-            if let Some(t) = &timers[handle] {
-                let mut data = t.borrow_mut();
-                data.state = State::Stopped;
+    /// Disables a timer and clears its counter.
+    ///
+    pub fn disable(&self, handle: SoftTimerHandle) -> Result<(), SoftTimerErr> {
+        self.with_timer(handle, |data| {
+            data.counter.store(C::ZERO, Ordering::Relaxed);
+            data.state = State::Disabled;
+        })
+    }
 
-                return Ok(());
-            }
-        } else {
+    /// Chains timer `to` to timer `from`, so `to` gets restarted with its
+    /// threshold whenever `from` expires. Start `to` once with start() and
+    /// stop() it to configure its threshold before it gets chained.
+    ///
+    pub fn chain(&self, from: SoftTimerHandle, to: SoftTimerHandle) -> Result<(), SoftTimerErr> {
+        self.with_timer(to, |_| ())?;
+        self.with_timer(from, |data| data.chained = Some(to))
+    }
+
+    /// Sets the unit of a timer in ticks, so its counter, threshold and
+    /// grace period count units of `unit` update() calls instead of single
+    /// ticks, e.g. for long timeouts on a fast tick. The unit is kept
+    /// across starts, the default is 1. Fails with
+    /// `SoftTimerErr::InvalidParameter` for a unit of zero.
+    ///
+    pub fn set_unit(&self, handle: SoftTimerHandle, unit: C) -> Result<(), SoftTimerErr> {
+        if C::ZERO == unit {
             return Err(SoftTimerErr::InvalidParameter);
         }
 
-        Err(SoftTimerErr::NoSuchTimer)
+        self.with_timer(handle, |data| {
+            data.unit = unit;
+            data.unit_ticks.set(C::ZERO);
+        })
     }
 
-    /// Disables a timer.
+    /// Gets the remaining time of a timer as `(whole_units, ticks)`, where
+    /// `whole_units` is the counter and `ticks` the number of ticks already
+    /// counted for the current unit, see set_unit(). A UI shows smooth
+    /// progress by this between two decrements of the counter.
     ///
-    pub fn disable(&self, handle: SoftTimerHandle) -> Result<(), SoftTimerErr> {
-        if handle < MAX_SOFT_COUNTER {
-            let timers: Ref<'_, [Option<RefCell<SoftTimerData>>; 16]> = self.timer.borrow();
+    pub fn remaining_subticks(&self, handle: SoftTimerHandle) -> Result<(C, C), SoftTimerErr> {
+        self.with_timer(handle, |data| {
+            (data.counter.load(Ordering::Relaxed), data.unit_ticks.get())
+        })
+    }
 
-            if let Some(t) = &timers[handle] {
-                let mut data = t.borrow_mut();
-                data.state = State::Disabled;
+    /// Sets the warning threshold of a timer, see is_warning(). The value
+    /// is kept across starts, zero (default) disables the warning.
+    ///
+    pub fn set_warn_at(&self, handle: SoftTimerHandle, warn_at: C) -> Result<(), SoftTimerErr> {
+        self.with_timer(handle, |data| data.warn_at = warn_at)
+    }
 
-                return Ok(());
-            }
-        } else {
-            return Err(SoftTimerErr::InvalidParameter);
-        }
+    /// Sets the minimum number of ticks between two expiries of a timer
+    /// reported by update_with() and the functions based on it, regardless
+    /// of how fast the timer expires. Expiries within the interval are not
+    /// reported but counted, see take_suppressed(). The value is kept across
+    /// starts, zero (default) reports every expiry.
+    ///
+    pub fn set_min_interval(
+        &self,
+        handle: SoftTimerHandle,
+        min_interval: C,
+    ) -> Result<(), SoftTimerErr> {
+        self.with_timer(handle, |data| data.min_interval = min_interval)
+    }
+
+    /// Takes the number of expiries suppressed by the minimum interval of
+    /// a timer since the last call, see set_min_interval().
+    ///
+    pub fn take_suppressed(&self, handle: SoftTimerHandle) -> Result<C, SoftTimerErr> {
+        self.with_timer(handle, |data| core::mem::take(&mut data.suppressed))
+    }
+
+    /// Sets the predicate deciding at each expiry of an auto restart timer
+    /// if it restarts, e.g. to keep blinking only in a certain mode. The
+    /// predicate is evaluated by update() and the functions based on it.
+    /// If it returns false, the timer expires as one shot timer. The
+    /// predicate is kept across starts, None (default) always restarts.
+    ///
+    pub fn set_restart_gate(
+        &self,
+        handle: SoftTimerHandle,
+        gate: Option<fn(SoftTimerHandle) -> bool>,
+    ) -> Result<(), SoftTimerErr> {
+        self.with_timer(handle, |data| data.restart_gate = gate)
+    }
 
-        Err(SoftTimerErr::NoSuchTimer)
+    /// Checks if a running timer is about to expire, i.e. its counter
+    /// dropped to or below the warning threshold but is not zero yet.
+    ///
+    pub fn is_warning(&self, handle: SoftTimerHandle) -> Result<bool, SoftTimerErr> {
+        self.with_timer(handle, |data| {
+            let counter = data.counter.load(Ordering::Relaxed);
+            (State::Running == data.state) && (C::ZERO < counter) && (counter <= data.warn_at)
+        })
     }
 
     /// Update all running timer
     ///
     pub fn update(&self) {
-        for (_idx, entry) in self.timer.borrow().iter().enumerate() {
-            if let Some(t) = entry {
-                let data = t.borrow_mut();
-
-                if State::Running == data.state {
-                    let counter = data.counter.load(Ordering::Relaxed);
-                    if 0 < counter {
-                        data.counter.fetch_sub(1, Ordering::Relaxed);
-                    }
-                }
-            }
-        }
+        self.update_with(|_| ());
     }
 
-    /// Get timer data
+    /// Updates all running timers by `ticks` ticks like calling update()
+    /// `ticks` times, see advance_to(). Fails with
+    /// `SoftTimerErr::LimitExceeded` without updating any timer if the
+    /// ticks counted since the expiry of an auto restart timer (see
+    /// check_and_count_expiries()) or the uptime would overflow.
     ///
-    pub fn get(&self, handle: SoftTimerHandle) -> Result<SoftTimerData, SoftTimerErr> {
-        if handle < MAX_SOFT_COUNTER {
-            let timers: Ref<'_, [Option<RefCell<SoftTimerData>>; 16]> = self.timer.borrow();
+    pub fn update_by(&self, ticks: C) -> Result<(), SoftTimerErr> {
+        if self.uptime.get().checked_add(ticks.to_u64()).is_none() {
+            return Err(SoftTimerErr::LimitExceeded);
+        }
 
-            if let Some(t) = &timers[handle] {
-                let data = t.borrow();
+        let overflows = self.slots().iter().flatten().any(|t| {
+            let data = RefCell::borrow(t);
 
-                return Ok(SoftTimerData {
-                    state: data.state,
-                    counter: AtomicUsize::new(data.counter.load(Ordering::Relaxed)),
-                    auto_restart: data.auto_restart,
-                    threshold: data.threshold,
-                });
-            } else {
-                return Err(SoftTimerErr::InvalidParameter);
-            }
+            (State::Running == data.state)
+                && data.is_overrunning()
+                && data.overrun.get().checked_add(ticks).is_none()
+        });
+
+        if overflows {
+            return Err(SoftTimerErr::LimitExceeded);
         }
-        Err(SoftTimerErr::NoSuchTimer)
-    }
-}
 
-// ************************************************************************************************
-// TESTS
-// ************************************************************************************************
+        self.advance_to(self.ticks.get().wrapping_add(ticks), |_| ());
+        Ok(())
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /// Starts timer `handle` as one shot timer and wraps it into a timeout
+    /// for a simple elapsed check.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lwos::softtimer::SofTimers;
+    ///
+    /// let timers = SofTimers::new();
+    /// let timeout = timers.timeout(timers.create().unwrap(), 1).unwrap();
+    /// assert!(!timeout.is_elapsed());
+    /// timers.update();
+    /// assert!(timeout.is_elapsed());
+    /// ```
+    pub fn timeout(
+        &self,
+        handle: SoftTimerHandle,
+        threshold: C,
+    ) -> Result<Timeout<'_, 'a, C>, SoftTimerErr> {
+        self.start(handle, threshold, false)?;
+
+        Ok(Timeout {
+            timers: self,
+            handle,
+        })
+    }
 
-    #[test]
-    fn softimer_register() {
-        let timers = SofTimers::new();
+    /// Sets the window for coalescing expiries. When a timer expires, all
+    /// running timers expiring within the next `window` ticks expire in the
+    /// same update, so the CPU needs to wake up only once. A window of zero
+    /// (default) disables coalescing.
+    ///
+    pub fn set_coalesce_window(&self, window: C) {
+        self.coalesce_window.set(window);
+    }
 
-        let st = timers.create().unwrap();
-        assert_eq!(timers.delete(st), Ok(()));
+    /// Sets the system action invoked by update() for each timer which
+    /// expires without auto restart, e.g. a reset handler for software
+    /// watchdogs which have to be restarted (kicked) before they expire.
+    /// The action runs before the `on_expired` callback of update_with().
+    /// It is a closure borrowed for the lifetime of the pool, so it can
+    /// carry its own state. Passing None removes the action.
+    ///
+    pub fn set_expiry_action(&self, action: Option<ExpiryAction<'a>>) {
+        self.expiry_action.set(action);
     }
 
-    #[test]
-    fn softimer_init() {
-        let timers = SofTimers::new();
-        let st_h = timers.create().unwrap();
-        let data: SoftTimerData = timers.get(st_h).unwrap();
+    /// Freezes the whole pool, e.g. during a debugger break. All updates
+    /// (update(), update_elapsed(), advance_to() ...) are ignored until
+    /// unfreeze(), including the tick of the pool. The timer states are not
+    /// touched, unlike stopping each timer.
+    ///
+    pub fn freeze(&self) {
+        self.frozen.set(true);
+    }
 
-        assert_eq!(data.state, State::Disabled);
-        assert!(!data.auto_restart);
-        assert_eq!(data.counter.load(Ordering::Relaxed), 0);
+    /// Resumes updates of a pool frozen by freeze().
+    ///
+    pub fn unfreeze(&self) {
+        self.frozen.set(false);
+    }
+
+    /// Checks if the pool is frozen, see freeze().
+    ///
+    pub fn is_frozen(&self) -> bool {
+        self.frozen.get()
+    }
+
+    /// Update all running timer and return true if at least one timer
+    /// expired by this update.
+    ///
+    pub fn update_tick(&self) -> bool {
+        let mut expired = false;
+        self.update_with(|_| expired = true);
+        expired
+    }
+
+    /// Update all running timer and return the number of timers which
+    /// expired by this update, e.g. for a single notification about a
+    /// batch of expiries.
+    ///
+    pub fn update_count_expired(&self) -> usize {
+        let mut expired = 0usize;
+        self.update_with(|_| expired += 1);
+        expired
+    }
+
+    /// Update all running timer and call `on_expired` for each timer which
+    /// expired by this update. The callback is invoked after all timers are
+    /// updated and no borrow of the pool is held, so it may start, stop or
+    /// delete timers of this pool, including the expired one.
+    ///
+    pub fn update_with(&self, on_expired: impl FnMut(SoftTimerHandle)) {
+        let mut expired = Expired::new();
+
+        if self.frozen.get() {
+            return;
+        }
+
+        let now = self.ticks.get().wrapping_add(C::ONE);
+        self.ticks.set(now);
+        self.uptime.set(self.uptime.get().wrapping_add(1));
+
+        let timers = self.slots();
+
+        for (handle, entry) in timers.iter().enumerate() {
+            if let Some(t) = entry {
+                let mut data = t.borrow_mut();
+                let fired = data.tick();
+                data.check_invariants();
+
+                if fired {
+                    expired.push(self.handle_of(handle), &mut data, now);
+                }
+            }
+        }
+
+        let window = self.coalesce_window.get();
+        if (0 < expired.count) && (C::ZERO < window) {
+            for (handle, entry) in timers.iter().enumerate() {
+                if let Some(t) = entry {
+                    let mut data = t.borrow_mut();
+                    let counter = data.counter.load(Ordering::Relaxed);
+
+                    if (State::Running == data.state)
+                        && (C::ZERO < counter)
+                        && (data.ticks_until(counter) <= window)
+                    {
+                        data.expire();
+                        expired.push(self.handle_of(handle), &mut data, now);
+                    }
+                }
+            }
+        }
+        drop(timers);
+
+        self.settle(&mut expired, on_expired);
+    }
+
+    /// Runs the restart gates, the chains, the expiry action and
+    /// `on_expired` for the timers expired by one update, see
+    /// update_with().
+    ///
+    fn settle(&self, expired: &mut Expired, mut on_expired: impl FnMut(SoftTimerHandle)) {
+        let count = expired.count;
+
+        for (index, handle) in expired.handles.iter().enumerate().take(count) {
+            let gate = self.with_timer(*handle, |data| data.restart_gate);
+
+            if let (false, Ok(Some(gate))) = (expired.one_shot[index], gate) {
+                if !gate(*handle) {
+                    let _ = self.with_timer(*handle, |data| data.auto_restart = false);
+                    expired.one_shot[index] = true;
+                }
+            }
+        }
+
+        for handle in expired.handles.iter().take(count) {
+            if let Ok(Some(next)) = self.with_timer(*handle, |data| data.chained) {
+                let _ = self.restart(next);
+            }
+        }
+
+        if let Some(action) = self.expiry_action.take() {
+            for (index, handle) in expired.handles.iter().enumerate().take(count) {
+                if expired.one_shot[index] && expired.reported[index] {
+                    action(*handle);
+                }
+            }
+            self.expiry_action.set(Some(action));
+        }
+
+        for (handle, _) in expired
+            .handles
+            .iter()
+            .zip(expired.reported)
+            .take(count)
+            .filter(|x| x.1)
+        {
+            on_expired(*handle);
+        }
+    }
+
+    /// Updates all running timers by the actually elapsed ticks like
+    /// update_by(), e.g. for an irregular tick source. An auto restart
+    /// timer which expired within the elapsed ticks gets restarted by
+    /// reading its signal with the ticks counted since its expiry (see
+    /// check_and_count_expiries()) taken off its next period, so its
+    /// average period stays correct. Expiries beyond one per period
+    /// between two signal reads are lost.
+    ///
+    pub fn update_elapsed(&self, elapsed_ticks: C) -> Result<(), SoftTimerErr> {
+        self.update_by(elapsed_ticks)?;
+
+        for t in self.slots().iter().flatten() {
+            let data = RefCell::borrow(t);
+
+            if (State::Running == data.state) && data.is_overrunning() {
+                data.realign.set(true);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Advances the pool to tick `now` (see now()) with the same result
+    /// as calling update_with(`on_expired`) once per tick in between,
+    /// including chains, coalescing, restart gates, the expiry action and
+    /// the minimum callback interval. The next event of each timer is kept
+    /// in a min-heap keyed by its tick, so only the timers with an event
+    /// get updated on their tick, and all others catch up at the end, in
+    /// O(n + k log n) for k events of n timers. A callback which accesses
+    /// the pool brings all timers up to date first, and the heap gets
+    /// rebuilt after it, as it may have changed any timer.
+    ///
+    pub fn advance_to(&self, now: C, mut on_expired: impl FnMut(SoftTimerHandle)) {
+        if self.frozen.get() {
+            return;
+        }
+
+        let left = now.wrapping_sub(self.ticks.get());
+        let mut heap = ExpiryHeap::new();
+        let mut tick = C::ZERO;
+        self.schedule_all(&mut heap, tick);
+
+        while let Some((next, _)) = heap
+            .peek()
+            .filter(|(next, _)| (tick < *next) && (*next <= left))
+        {
+            self.pass(next - tick);
+            tick = next;
+
+            let now = self.ticks.get();
+            let mut expired = Expired::new();
+            let mut due = [0usize; MAX_SOFT_COUNTER];
+            let mut count = 0usize;
+            let timers = self.timer.borrow();
+
+            while let Some((_, index)) = heap.peek().filter(|(at, _)| tick == *at) {
+                heap.set(index, None);
+                due[count] = index;
+                count += 1;
+            }
+
+            for index in due.iter().take(count) {
+                if let Some(t) = &timers[*index] {
+                    let mut data = t.borrow_mut();
+                    self.catch_up(*index, &mut data, C::ONE);
+                    let fired = data.tick();
+                    data.check_invariants();
+                    self.synced[*index].set(now);
+
+                    if fired {
+                        expired.push(self.handle_of(*index), &mut data, now);
+                    }
+                    heap.set(*index, data.event_tick(tick));
+                }
+            }
+
+            let window = self.coalesce_window.get();
+            if (0 < expired.count) && (C::ZERO < window) {
+                count = 0;
+                while let Some((_, index)) = heap.peek().filter(|(at, _)| *at - tick <= window) {
+                    heap.set(index, None);
+                    due[count] = index;
+                    count += 1;
+                }
+                due[..count].sort_unstable();
+
+                for index in due.iter().take(count) {
+                    if let Some(t) = &timers[*index] {
+                        let mut data = t.borrow_mut();
+                        self.catch_up(*index, &mut data, C::ZERO);
+                        let counter = data.counter.load(Ordering::Relaxed);
+
+                        if (State::Running == data.state)
+                            && (C::ZERO < counter)
+                            && (data.ticks_until(counter) <= window)
+                        {
+                            data.expire();
+                            expired.push(self.handle_of(*index), &mut data, now);
+                        }
+                        heap.set(*index, data.event_tick(tick));
+                    }
+                }
+            }
+
+            count = 0;
+            for handle in expired.handles.iter().take(expired.count) {
+                let chained = timers[*handle & HANDLE_INDEX_MASK]
+                    .as_ref()
+                    .and_then(|t| RefCell::borrow(t).chained);
+
+                if let Some(Ok(index)) = chained.map(|next| self.index_of(next)) {
+                    if let Some(t) = &timers[index] {
+                        self.catch_up(index, &mut t.borrow_mut(), C::ZERO);
+                    }
+                    due[count] = index;
+                    count += 1;
+                }
+            }
+            drop(timers);
+
+            let mut observed = false;
+            self.settle(&mut expired, |handle| {
+                self.lagging.set(true);
+                on_expired(handle);
+                observed |= !self.lagging.replace(false);
+            });
+
+            if observed {
+                self.schedule_all(&mut heap, tick);
+            } else {
+                let timers = self.timer.borrow();
+                for index in due.iter().take(count) {
+                    let event = timers[*index]
+                        .as_ref()
+                        .and_then(|t| RefCell::borrow(t).event_tick(tick));
+                    heap.set(*index, event);
+                }
+            }
+
+            if self.frozen.get() {
+                break;
+            }
+        }
+
+        if !self.frozen.get() {
+            self.pass(left - tick);
+        }
+        self.catch_up_all();
+    }
+
+    /// Advances the pool tick by `ticks` ticks without updating the
+    /// timers, see advance_to().
+    ///
+    fn pass(&self, ticks: C) {
+        self.ticks.set(self.ticks.get().wrapping_add(ticks));
+        self.uptime
+            .set(self.uptime.get().wrapping_add(ticks.to_u64()));
+    }
+
+    /// Brings timer `index` up to `behind` ticks before the pool tick,
+    /// see advance_to().
+    ///
+    fn catch_up(&self, index: usize, data: &mut SoftTimerData<C>, behind: C) {
+        let tick = self.ticks.get().wrapping_sub(behind);
+
+        data.skip(tick.wrapping_sub(self.synced[index].get()));
+        data.check_invariants();
+        self.synced[index].set(tick);
+    }
+
+    /// Brings all timers up to the pool tick, see advance_to().
+    ///
+    fn catch_up_all(&self) {
+        for (index, entry) in self.timer.borrow().iter().enumerate() {
+            if let Some(t) = entry {
+                self.catch_up(index, &mut t.borrow_mut(), C::ZERO);
+            }
+        }
+    }
+
+    /// Fills `heap` with the next event of all timers after tick `tick`,
+    /// which is the current pool tick, see advance_to().
+    ///
+    fn schedule_all(&self, heap: &mut ExpiryHeap<C>, tick: C) {
+        for (index, entry) in self.slots().iter().enumerate() {
+            self.synced[index].set(self.ticks.get());
+            heap.set(
+                index,
+                entry
+                    .as_ref()
+                    .and_then(|t| RefCell::borrow(t).event_tick(tick)),
+            );
+        }
+    }
+
+    /// Borrows the timer slots. All timers are brought up to the pool tick
+    /// first, if advance_to() left them behind for a callback.
+    ///
+    fn slots(&self) -> Ref<'_, Slots<C>> {
+        if self.lagging.replace(false) {
+            self.catch_up_all();
+        }
+        self.timer.borrow()
+    }
+
+    /// Borrows the timer slots mutably, see slots().
+    ///
+    fn slots_mut(&self) -> RefMut<'_, Slots<C>> {
+        if self.lagging.replace(false) {
+            self.catch_up_all();
+        }
+        self.timer.borrow_mut()
+    }
+
+    /// Gets the current tick of the pool, i.e. the number of updates done.
+    /// The tick wraps around on overflow.
+    ///
+    pub fn now(&self) -> C {
+        self.ticks.get()
+    }
+
+    /// Gets the number of ticks since the pool was created, like now() but
+    /// without wrapping around on targets with a small counter.
+    ///
+    pub fn uptime_ticks(&self) -> u64 {
+        self.uptime.get()
+    }
+
+    /// Gets the time since the pool was created in milliseconds for an
+    /// update() rate of `tick_hz`. Returns 0 for a rate of 0.
+    ///
+    pub fn uptime_millis(&self, tick_hz: C) -> u64 {
+        let uptime = self.uptime.get();
+        let hz = tick_hz.to_u64();
+
+        match (uptime.checked_div(hz), uptime.checked_rem(hz)) {
+            (Some(seconds), Some(rest)) => seconds * 1000 + rest * 1000 / hz,
+            _ => 0,
+        }
+    }
+
+    /// Gets the tick (see now()) at which the timer expired last, or `None`
+    /// if it didn't expire since it was (re)started. The difference to the
+    /// current tick is the latency of handling the expiry.
+    ///
+    pub fn expired_at(&self, handle: SoftTimerHandle) -> Result<Option<C>, SoftTimerErr> {
+        self.with_timer(handle, |data| data.expired_at)
+    }
+
+    /// Checks the expiry latch of a timer. The latch is set by each expiry
+    /// in update() or advance_to() and stays set until clear_latch(), also
+    /// if an auto restart timer restarts by reading its signal. So an
+    /// expiry isn't lost if the timer is not polled in time.
+    ///
+    pub fn expired_latch(&self, handle: SoftTimerHandle) -> Result<bool, SoftTimerErr> {
+        self.with_timer(handle, |data| data.latched)
+    }
+
+    /// Clears the expiry latch of a timer, see expired_latch().
+    ///
+    pub fn clear_latch(&self, handle: SoftTimerHandle) -> Result<(), SoftTimerErr> {
+        self.with_timer(handle, |data| data.latched = false)
+    }
+
+    /// Gets the first timer with the given tag, see start_tagged().
+    ///
+    pub fn find_by_tag(&self, tag: u16) -> Option<SoftTimerHandle> {
+        self.timer
+            .borrow()
+            .iter()
+            .position(|entry| {
+                entry
+                    .as_ref()
+                    .is_some_and(|t| Some(tag) == RefCell::borrow(t).tag)
+            })
+            .map(|index| self.handle_of(index))
+    }
+
+    /// Gets the expired timer with the smallest threshold, i.e. the most
+    /// time sensitive one. Unlike is_signaled(), this does not restart an
+    /// auto restart timer.
+    ///
+    pub fn next_expired(&self) -> Option<SoftTimerHandle> {
+        let mut next: Option<(SoftTimerHandle, C)> = None;
+
+        for (handle, entry) in self.slots().iter().enumerate() {
+            if let Some(t) = entry {
+                let data = RefCell::borrow(t);
+
+                if (State::Running == data.state)
+                    && (C::ZERO == data.counter.load(Ordering::Relaxed))
+                    && next.is_none_or(|(_, threshold)| data.threshold < threshold)
+                {
+                    next = Some((handle, data.threshold));
+                }
+            }
+        }
+
+        next.map(|(index, _)| self.handle_of(index))
+    }
+
+    /// Gets the running timer with the most ticks elapsed since it was
+    /// started or restarted, e.g. to spot a timeout which should have
+    /// fired already. On equal elapsed ticks the lower handle wins.
+    ///
+    pub fn longest_running(&self) -> Option<SoftTimerHandle> {
+        let mut longest: Option<(SoftTimerHandle, C)> = None;
+
+        for (handle, entry) in self.slots().iter().enumerate() {
+            if let Some(t) = entry {
+                let data = RefCell::borrow(t);
+                let elapsed = data.elapsed();
+
+                if (State::Running == data.state) && longest.is_none_or(|(_, most)| elapsed > most)
+                {
+                    longest = Some((handle, elapsed));
+                }
+            }
+        }
+
+        longest.map(|(index, _)| self.handle_of(index))
+    }
+
+    /// Checks if any timer is running, i.e. if the pool needs updates at
+    /// all. The tick interrupt can be disabled to save power otherwise.
+    ///
+    pub fn any_running(&self) -> bool {
+        self.timer
+            .borrow()
+            .iter()
+            .flatten()
+            .any(|t| State::Running == RefCell::borrow(t).state)
+    }
+
+    /// Gets the number of running timers.
+    ///
+    pub fn running_count(&self) -> usize {
+        self.timer
+            .borrow()
+            .iter()
+            .flatten()
+            .filter(|t| State::Running == RefCell::borrow(t).state)
+            .count()
+    }
+
+    /// Gets the highest number of simultaneously running timers since the
+    /// pool was created or reset_peak() was called, e.g. to check if
+    /// MAX_SOFT_COUNTER fits the application.
+    ///
+    pub fn peak_running(&self) -> usize {
+        self.peak_running.get()
+    }
+
+    /// Resets the peak of running timers to the number of currently running
+    /// timers, see peak_running().
+    ///
+    pub fn reset_peak(&self) {
+        self.peak_running.set(self.running_count());
+    }
+
+    /// Raises the peak of running timers to the current number of running
+    /// timers if exceeded.
+    ///
+    fn record_peak(&self) {
+        let running = self.running_count();
+
+        if running > self.peak_running.get() {
+            self.peak_running.set(running);
+        }
+    }
+
+    /// Iterates over all timers as `(handle, counter)` pairs. The pool
+    /// stays borrowed until the iterator is dropped, so all pairs are
+    /// taken from the same state of the pool.
+    ///
+    pub fn iter_remaining(&self) -> impl Iterator<Item = (SoftTimerHandle, C)> + use<'_, 'a, C> {
+        RemainingIter {
+            timers: self,
+            slots: self.slots(),
+            index: 0,
+        }
+    }
+
+    /// Gets the number of timers per state in a single pass, indexed
+    /// by `State as usize` (Disabled, Stopped, Running).
+    ///
+    pub fn count_by_state(&self) -> [usize; 3] {
+        let mut counts = [0usize; 3];
+
+        for t in self.slots().iter().flatten() {
+            counts[RefCell::borrow(t).state as usize] += 1;
+        }
+
+        counts
+    }
+
+    /// Gets the number of update() calls until the next timer event, i.e.
+    /// a timer expiring or the grace period of an expired timer ending,
+    /// for programming a one shot hardware timer in tickless operation.
+    /// Returns None if no update is needed, e.g. as all running timers
+    /// without grace period are expired and wait for being read.
+    ///
+    pub fn next_wakeup(&self) -> Option<C> {
+        self.timer
+            .borrow()
+            .iter()
+            .flatten()
+            .filter_map(|t| RefCell::borrow(t).next_event())
+            .min()
+    }
+
+    /// Fills `out` with the handles of all running timers, ordered by
+    /// their remaining ticks (soonest first). Returns the number of
+    /// handles written. If `out` is too small, only the soonest timers
+    /// are kept.
+    ///
+    pub fn pending_sorted(&self, out: &mut [SoftTimerHandle]) -> usize {
+        let mut remaining: [C; MAX_SOFT_COUNTER] = [C::ZERO; MAX_SOFT_COUNTER];
+        let capacity = out.len().min(MAX_SOFT_COUNTER);
+        let mut count = 0usize;
+
+        for (handle, entry) in self.slots().iter().enumerate() {
+            if let Some(t) = entry {
+                let data = t.borrow();
+
+                if State::Running != data.state {
+                    continue;
+                }
+
+                let ticks = data.ticks_until(data.counter.load(Ordering::Relaxed));
+                let mut pos = count;
+                while (0 < pos) && (remaining[pos - 1] > ticks) {
+                    pos -= 1;
+                }
+
+                if pos < capacity {
+                    let mut idx = count.min(capacity - 1);
+                    while idx > pos {
+                        out[idx] = out[idx - 1];
+                        remaining[idx] = remaining[idx - 1];
+                        idx -= 1;
+                    }
+                    out[pos] = self.handle_of(handle);
+                    remaining[pos] = ticks;
+
+                    if count < capacity {
+                        count += 1;
+                    }
+                }
+            }
+        }
+
+        count
+    }
+
+    /// Checks if a timer is signaled. An auto restart timer is restarted
+    /// by this call if it is signaled.
+    ///
+    pub fn is_signaled(&self, handle: SoftTimerHandle) -> Result<bool, SoftTimerErr> {
+        self.with_timer(handle, |data| {
+            matches!(data.get_signal_state(), SignalState::Signaled)
+        })
+    }
+
+    /// Gets the number of expiries of a timer since the last check. Unlike
+    /// is_signaled(), an auto restart timer read late by several periods
+    /// reports all of them and gets restarted in phase with its original
+    /// period, so no periods are dropped. Timers with grace period or
+    /// without auto restart report 1 while being expired.
+    ///
+    pub fn check_and_count_expiries(&self, handle: SoftTimerHandle) -> Result<usize, SoftTimerErr> {
+        self.with_timer(handle, SoftTimerData::take_expiries)
+    }
+
+    /// Blocks until the given timer signals. The `tick` closure is called
+    /// before each update(), e.g. to sleep for one tick period.
+    /// Fails with `SoftTimerErr::Disabled` if the timer is not running.
+    ///
+    #[cfg(feature = "std")]
+    pub fn wait_blocking(
+        &self,
+        handle: SoftTimerHandle,
+        mut tick: impl FnMut(),
+    ) -> Result<(), SoftTimerErr> {
+        if State::Running != self.with_timer(handle, |data| data.state)? {
+            return Err(SoftTimerErr::Disabled);
+        }
+
+        while !self.is_signaled(handle)? {
+            tick();
+            self.update();
+        }
+
+        Ok(())
+    }
+
+    /// Takes a snapshot of all timers, indexed by handle. Unused handles
+    /// are `None`.
+    ///
+    pub fn snapshot_all(&self) -> [Option<Snapshot<C>>; MAX_SOFT_COUNTER] {
+        let mut snapshots = [None; MAX_SOFT_COUNTER];
+
+        for (index, (snapshot, entry)) in snapshots.iter_mut().zip(self.slots().iter()).enumerate()
+        {
+            *snapshot = entry.as_ref().map(|t| {
+                let data = RefCell::borrow(t);
+                Snapshot {
+                    handle: self.handle_of(index),
+                    state: data.state,
+                    counter: data.counter.load(Ordering::Relaxed),
+                    threshold: data.threshold,
+                }
+            });
+        }
+
+        snapshots
+    }
+
+    /// Compares two results of snapshot_all() and fills `out` with the
+    /// handles of timers which were created, deleted or changed their state,
+    /// counter or threshold. Returns the number of handles written, limited
+    /// by the size of `out`.
+    ///
+    pub fn diff(
+        prev: &[Option<Snapshot<C>>],
+        now: &[Option<Snapshot<C>>],
+        out: &mut [SoftTimerHandle],
+    ) -> usize {
+        let mut count = 0usize;
+
+        for (before, after) in prev.iter().zip(now.iter()) {
+            if before == after {
+                continue;
+            }
+
+            if let Some(snapshot) = after.or(*before) {
+                match out.get_mut(count) {
+                    Some(entry) => *entry = snapshot.handle,
+                    None => break,
+                }
+                count += 1;
+            }
+        }
+
+        count
+    }
+
+    /// Writes the configuration of all timers into `out`: a count byte
+    /// followed by one record of RECORD_SIZE bytes per timer. Returns the
+    /// number of bytes written. Not included are chains, the expiry action
+    /// of the pool, expiry ticks, latches and the runtime state of grace
+    /// periods, drift compensation and the minimum callback interval.
+    /// Fails with `SoftTimerErr::LimitExceeded` if `out` is too small or a
+    /// value exceeds 32 bits, and with `SoftTimerErr::InvalidParameter` if
+    /// a timer has a restart gate, as functions can't be persisted.
+    ///
+    pub fn serialize(&self, out: &mut [u8]) -> Result<usize, SoftTimerErr> {
+        let timers = self.slots();
+        let count = timers.iter().flatten().count();
+        let size = 1 + count * RECORD_SIZE;
+
+        if out.len() < size {
+            return Err(SoftTimerErr::LimitExceeded);
+        }
+
+        out[0] = count as u8;
+        let records = out[1..size].chunks_exact_mut(RECORD_SIZE);
+        let entries = timers
+            .iter()
+            .enumerate()
+            .filter_map(|(i, t)| Some((i, t.as_ref()?)));
+
+        for (record, (index, t)) in records.zip(entries) {
+            let data = RefCell::borrow(t);
+            let counter = data.counter.load(Ordering::Relaxed);
+
+            if data.restart_gate.is_some() {
+                return Err(SoftTimerErr::InvalidParameter);
+            }
+
+            record[0] = index as u8;
+            record[1] = data.state as u8;
+            record[2] = if data.auto_restart {
+                RECORD_AUTO_RESTART
+            } else {
+                0
+            } | if data.tag.is_some() { RECORD_TAGGED } else { 0 };
+            record[3..5].copy_from_slice(&data.tag.unwrap_or(0).to_le_bytes());
+            for (field, value) in record[5..].chunks_exact_mut(4).zip([
+                data.threshold,
+                counter,
+                data.grace,
+                data.warn_at,
+                data.unit,
+                data.unit_ticks.get(),
+                data.min_interval,
+            ]) {
+                let value =
+                    u32::try_from(value.to_u64()).map_err(|_| SoftTimerErr::LimitExceeded)?;
+                field.copy_from_slice(&value.to_le_bytes());
+            }
+        }
+
+        Ok(size)
+    }
+
+    /// Replaces all timers by the ones of a representation written by
+    /// serialize(). Existing handles become invalid, the restored timers
+    /// keep their slots. Fails with `SoftTimerErr::InvalidParameter`
+    /// without changing the pool if the data is malformed.
+    ///
+    pub fn deserialize(&self, data: &[u8]) -> Result<(), SoftTimerErr> {
+        let count = usize::from(*data.first().ok_or(SoftTimerErr::InvalidParameter)?);
+
+        if (MAX_SOFT_COUNTER < count) || (data.len() != 1 + count * RECORD_SIZE) {
+            return Err(SoftTimerErr::InvalidParameter);
+        }
+
+        let mut restored: [Option<SoftTimerData<C>>; MAX_SOFT_COUNTER] = Default::default();
+
+        for record in data[1..].chunks_exact(RECORD_SIZE) {
+            let mut values = record[5..].chunks_exact(4).map(|field| {
+                C::from_u64(u64::from(u32::from_le_bytes([
+                    field[0], field[1], field[2], field[3],
+                ])))
+            });
+            let mut next = || {
+                values
+                    .next()
+                    .flatten()
+                    .ok_or(SoftTimerErr::InvalidParameter)
+            };
+            let (threshold, counter, grace) = (next()?, next()?, next()?);
+            let (warn_at, unit, unit_ticks, min_interval) = (next()?, next()?, next()?, next()?);
+            let flags = record[2];
+            let state = match record[1] {
+                0 => State::Disabled,
+                1 => State::Stopped,
+                2 => State::Running,
+                _ => return Err(SoftTimerErr::InvalidParameter),
+            };
+
+            let entry = restored
+                .get_mut(usize::from(record[0]))
+                .filter(|entry| entry.is_none())
+                .ok_or(SoftTimerErr::InvalidParameter)?;
+
+            if (0 != flags & !(RECORD_AUTO_RESTART | RECORD_TAGGED))
+                || (threshold < counter)
+                || ((State::Disabled == state) && (C::ZERO < counter))
+                || (unit <= unit_ticks)
+            {
+                return Err(SoftTimerErr::InvalidParameter);
+            }
+
+            *entry = Some(SoftTimerData {
+                state,
+                counter: AtomicCounter::new(counter),
+                threshold,
+                auto_restart: 0 != flags & RECORD_AUTO_RESTART,
+                grace,
+                warn_at,
+                tag: (0 != flags & RECORD_TAGGED)
+                    .then_some(u16::from_le_bytes([record[3], record[4]])),
+                unit,
+                unit_ticks: Cell::new(unit_ticks),
+                min_interval,
+                ..SoftTimerData::new()
+            });
+        }
+
+        let mut timers = self.slots_mut();
+        for (index, (slot, data)) in timers.iter_mut().zip(restored).enumerate() {
+            if slot.is_some() {
+                let generation = &self.generations[index];
+                generation.set(generation.get().wrapping_add(1));
+            }
+            *slot = data.map(RefCell::new);
+        }
+
+        Ok(())
+    }
+
+    /// Get timer data
+    ///
+    pub fn get(&self, handle: SoftTimerHandle) -> Result<SoftTimerData<C>, SoftTimerErr> {
+        self.index_of(handle).map_err(|err| match err {
+            SoftTimerErr::InvalidParameter => SoftTimerErr::NoSuchTimer,
+            SoftTimerErr::NoSuchTimer => SoftTimerErr::InvalidParameter,
+            err => err,
+        })?;
+
+        self.with_timer(handle, |data| SoftTimerData {
+            counter: AtomicCounter::new(data.counter.load(Ordering::Relaxed)),
+            realign: Cell::new(data.realign.get()),
+            overrun: Cell::new(data.overrun.get()),
+            unit_ticks: Cell::new(data.unit_ticks.get()),
+            ..*data
+        })
+    }
+
+    /// Calls `f` with the data of the given timer.
+    ///
+    fn with_timer<R>(
+        &self,
+        handle: SoftTimerHandle,
+        f: impl FnOnce(&mut SoftTimerData<C>) -> R,
+    ) -> Result<R, SoftTimerErr> {
+        let index = self.index_of(handle)?;
+
+        self.slots()[index]
+            .as_ref()
+            .map(|t| {
+                let mut data = t.borrow_mut();
+                let result = f(&mut data);
+                data.check_invariants();
+                result
+            })
+            .ok_or(SoftTimerErr::NoSuchTimer)
+    }
+
+    /// Builds the handle of the timer in the given slot.
+    ///
+    fn handle_of(&self, index: usize) -> SoftTimerHandle {
+        (SoftTimerHandle::from(self.generations[index].get()) << HANDLE_INDEX_BITS) | index
+    }
+
+    /// Gets the slot index of a handle. Fails with `SoftTimerErr::StaleHandle`
+    /// if the slot was reused since the handle was created.
+    ///
+    fn index_of(&self, handle: SoftTimerHandle) -> Result<usize, SoftTimerErr> {
+        let index = handle & HANDLE_INDEX_MASK;
+
+        if MAX_SOFT_COUNTER <= index {
+            return Err(SoftTimerErr::InvalidParameter);
+        }
+
+        if self.slots()[index].is_none() {
+            return Err(SoftTimerErr::NoSuchTimer);
+        }
+
+        if self.handle_of(index) != handle {
+            return Err(SoftTimerErr::StaleHandle);
+        }
+
+        Ok(index)
+    }
+}
+
+impl<C: TimerCounter> Timeout<'_, '_, C> {
+    /// Checks if the timeout elapsed. A deleted timer never elapses.
+    ///
+    pub fn is_elapsed(&self) -> bool {
+        Ok(true) == self.timers.is_signaled(self.handle)
+    }
+
+    /// Restarts the timeout with its full threshold.
+    ///
+    pub fn reset(&self) {
+        let _ = self.timers.restart(self.handle);
+    }
+
+    /// Gets the handle of the timer.
+    ///
+    pub fn handle(&self) -> SoftTimerHandle {
+        self.handle
+    }
+}
+
+impl Expired {
+    fn new() -> Self {
+        Expired {
+            handles: [0; MAX_SOFT_COUNTER],
+            one_shot: [false; MAX_SOFT_COUNTER],
+            reported: [false; MAX_SOFT_COUNTER],
+            count: 0,
+        }
+    }
+
+    /// Records the expiry of timer `handle` at tick `now`.
+    ///
+    fn push<C: TimerCounter>(
+        &mut self,
+        handle: SoftTimerHandle,
+        data: &mut SoftTimerData<C>,
+        now: C,
+    ) {
+        data.expired_at = Some(now);
+        data.latched = true;
+        self.handles[self.count] = handle;
+        self.one_shot[self.count] = !data.auto_restart;
+        self.reported[self.count] = data.allow_callback(now);
+        self.count += 1;
+    }
+}
+
+impl<C: TimerCounter> ExpiryHeap<C> {
+    fn new() -> Self {
+        ExpiryHeap {
+            items: [(C::ZERO, 0); MAX_SOFT_COUNTER],
+            position: [None; MAX_SOFT_COUNTER],
+            len: 0,
+        }
+    }
+
+    /// Gets the `(tick, index)` entry with the lowest tick.
+    ///
+    fn peek(&self) -> Option<(C, usize)> {
+        (0 < self.len).then(|| self.items[0])
+    }
+
+    /// Sets the event tick of timer `index`, None removes its entry.
+    ///
+    fn set(&mut self, index: usize, tick: Option<C>) {
+        if let Some(at) = self.position[index].take() {
+            self.len -= 1;
+
+            if at < self.len {
+                self.items[at] = self.items[self.len];
+                self.position[self.items[at].1] = Some(at);
+                let at = self.sift_down(at);
+                self.sift_up(at);
+            }
+        }
+
+        if let Some(tick) = tick {
+            self.items[self.len] = (tick, index);
+            self.position[index] = Some(self.len);
+            self.len += 1;
+            self.sift_up(self.len - 1);
+        }
+    }
+
+    fn sift_up(&mut self, mut at: usize) {
+        while 0 < at {
+            let parent = (at - 1) / 2;
+
+            if self.items[parent] <= self.items[at] {
+                break;
+            }
+            self.swap(parent, at);
+            at = parent;
+        }
+    }
+
+    /// Moves the entry at `at` down to its place, which gets returned.
+    ///
+    fn sift_down(&mut self, mut at: usize) -> usize {
+        loop {
+            let mut least = at;
+
+            for child in [2 * at + 1, 2 * at + 2] {
+                if (child < self.len) && (self.items[child] < self.items[least]) {
+                    least = child;
+                }
+            }
+            if least == at {
+                return at;
+            }
+            self.swap(at, least);
+            at = least;
+        }
+    }
+
+    fn swap(&mut self, a: usize, b: usize) {
+        self.items.swap(a, b);
+        self.position[self.items[a].1] = Some(a);
+        self.position[self.items[b].1] = Some(b);
+    }
+}
+
+impl<C: TimerCounter> Iterator for RemainingIter<'_, '_, C> {
+    type Item = (SoftTimerHandle, C);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.index < MAX_SOFT_COUNTER {
+            let index = self.index;
+            self.index += 1;
+
+            if let Some(t) = &self.slots[index] {
+                let counter = RefCell::borrow(t).counter.load(Ordering::Relaxed);
+                return Some((self.timers.handle_of(index), counter));
+            }
+        }
+
+        None
+    }
+}
+
+impl<C: TimerCounter> TimerSignals for SofTimers<'_, C> {
+    fn is_signaled(&self, handle: SoftTimerHandle) -> Result<bool, SoftTimerErr> {
+        SofTimers::is_signaled(self, handle)
+    }
+}
+
+impl<C: TimerCounter> Default for SofTimers<'_, C> {
+    fn default() -> Self {
+        Self::with_counter()
+    }
+}
+
+/// Implements TimerCounter for an unsigned integer type and AtomicCounter
+/// for its atomic type, if the target supports atomics of `width`.
+macro_rules! impl_timer_counter {
+    ($type:ty, $atomic:ty, $width:literal) => {
+        #[cfg(target_has_atomic = $width)]
+        impl AtomicCounter<$type> for $atomic {
+            fn new(value: $type) -> Self {
+                <$atomic>::new(value)
+            }
+
+            fn load(&self, order: Ordering) -> $type {
+                <$atomic>::load(self, order)
+            }
+
+            fn store(&self, value: $type, order: Ordering) {
+                <$atomic>::store(self, value, order)
+            }
+
+            fn fetch_sub(&self, value: $type, order: Ordering) -> $type {
+                <$atomic>::fetch_sub(self, value, order)
+            }
+        }
+
+        #[cfg(target_has_atomic = $width)]
+        impl TimerCounter for $type {
+            type Atomic = $atomic;
+
+            const ZERO: Self = 0;
+            const ONE: Self = 1;
+
+            fn saturating_add(self, rhs: Self) -> Self {
+                <$type>::saturating_add(self, rhs)
+            }
+
+            fn saturating_sub(self, rhs: Self) -> Self {
+                <$type>::saturating_sub(self, rhs)
+            }
+
+            fn saturating_mul(self, rhs: Self) -> Self {
+                <$type>::saturating_mul(self, rhs)
+            }
+
+            fn wrapping_add(self, rhs: Self) -> Self {
+                <$type>::wrapping_add(self, rhs)
+            }
+
+            fn wrapping_sub(self, rhs: Self) -> Self {
+                <$type>::wrapping_sub(self, rhs)
+            }
+
+            fn checked_add(self, rhs: Self) -> Option<Self> {
+                <$type>::checked_add(self, rhs)
+            }
+
+            fn checked_div(self, rhs: Self) -> Option<Self> {
+                <$type>::checked_div(self, rhs)
+            }
+
+            fn checked_rem(self, rhs: Self) -> Option<Self> {
+                <$type>::checked_rem(self, rhs)
+            }
+
+            fn to_u64(self) -> u64 {
+                self as u64
+            }
+
+            fn from_u64(value: u64) -> Option<Self> {
+                Self::try_from(value).ok()
+            }
+        }
+    };
+}
+
+impl_timer_counter!(u16, core::sync::atomic::AtomicU16, "16");
+impl_timer_counter!(u32, core::sync::atomic::AtomicU32, "32");
+impl_timer_counter!(u64, core::sync::atomic::AtomicU64, "64");
+impl_timer_counter!(usize, core::sync::atomic::AtomicUsize, "ptr");
+
+// ************************************************************************************************
+// TESTS
+// ************************************************************************************************
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::sync::atomic::AtomicUsize;
+
+    #[test]
+    fn softimer_register() {
+        let timers = SofTimers::new();
+
+        let st = timers.create().unwrap();
+        assert_eq!(timers.delete(st), Ok(()));
+    }
+
+    #[test]
+    fn softimer_init() {
+        let timers = SofTimers::new();
+        let st_h = timers.create().unwrap();
+        let data: SoftTimerData = timers.get(st_h).unwrap();
+
+        assert_eq!(data.state, State::Disabled);
+        assert!(!data.auto_restart);
+        assert_eq!(data.counter.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    fn softimer_modify() {
+        let timers = SofTimers::new();
+        let h = timers.create().unwrap();
+        let data: SoftTimerData = timers.get(h).unwrap();
+
+        assert_eq!(data.state, State::Disabled);
+
+        assert_eq!(timers.start(h, 1234, true), Ok(()));
+        let data: SoftTimerData = timers.get(h).unwrap();
+
+        assert_eq!(data.state, State::Running);
+        assert!(data.auto_restart);
+        assert_eq!(data.counter.load(Ordering::Relaxed), 1234);
+
+        assert_eq!(timers.stop(h), Ok(()));
+
+        let data: SoftTimerData = timers.get(h).unwrap();
+        assert_eq!(data.state, State::Stopped);
+        assert!(data.auto_restart);
+        assert_eq!(data.counter.load(Ordering::Relaxed), 1234);
+
+        assert_eq!(timers.delete(h), Ok(()));
+    }
+    #[test]
+    fn softtimer_disable() {
+        let timers = SofTimers::new();
+        let h = timers.create().unwrap();
+        assert_eq!(timers.disable(h), Ok(()));
+        let data: SoftTimerData = timers.get(h).unwrap();
+        assert_eq!(data.state, State::Disabled);
+    }
+
+    #[test]
+    fn softtimer_update() {
+        let timers = SofTimers::new();
+        let h1 = timers.create().unwrap();
+        let h2 = timers.create().unwrap();
+
+        assert_eq!(timers.start(h1, 3, true), Ok(()));
+        assert_eq!(timers.start(h2, 1, true), Ok(()));
+
+        let data: SoftTimerData = timers.get(h1).unwrap();
+        assert_eq!(data.counter.load(Ordering::Relaxed), 3);
+        let data: SoftTimerData = timers.get(h2).unwrap();
+        assert_eq!(data.counter.load(Ordering::Relaxed), 1);
+
+        timers.update();
+        let data: SoftTimerData = timers.get(h1).unwrap();
+        assert_eq!(data.counter.load(Ordering::Relaxed), 2);
+        let data: SoftTimerData = timers.get(h2).unwrap();
+        assert_eq!(data.counter.load(Ordering::Relaxed), 0);
+
+        timers.update();
+        let data: SoftTimerData = timers.get(h1).unwrap();
+        assert_eq!(data.counter.load(Ordering::Relaxed), 1);
+        let data: SoftTimerData = timers.get(h2).unwrap();
+        assert_eq!(data.counter.load(Ordering::Relaxed), 0);
+
+        timers.update();
+        let data: SoftTimerData = timers.get(h1).unwrap();
+        assert_eq!(data.counter.load(Ordering::Relaxed), 0);
+        let data: SoftTimerData = timers.get(h2).unwrap();
+        assert_eq!(data.counter.load(Ordering::Relaxed), 0);
+
+        assert_eq!(timers.restart(h1), Ok(()));
+        assert_eq!(timers.restart(h2), Ok(()));
+
+        let data: SoftTimerData = timers.get(h1).unwrap();
+        assert_eq!(data.counter.load(Ordering::Relaxed), 3);
+        let data: SoftTimerData = timers.get(h2).unwrap();
+        assert_eq!(data.counter.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn softtimer_pending_sorted() {
+        let timers = SofTimers::new();
+        let h5 = timers.create().unwrap();
+        let h1 = timers.create().unwrap();
+        let h3 = timers.create().unwrap();
+        let stopped = timers.create().unwrap();
+
+        assert_eq!(timers.start(h5, 5, false), Ok(()));
+        assert_eq!(timers.start(h1, 1, false), Ok(()));
+        assert_eq!(timers.start(h3, 3, false), Ok(()));
+        assert_eq!(timers.start(stopped, 2, false), Ok(()));
+        assert_eq!(timers.stop(stopped), Ok(()));
+
+        let mut out = [0usize; MAX_SOFT_COUNTER];
+        assert_eq!(timers.pending_sorted(&mut out), 3);
+        assert_eq!(out[..3], [h1, h3, h5]);
+
+        let mut short = [0usize; 2];
+        assert_eq!(timers.pending_sorted(&mut short), 2);
+        assert_eq!(short, [h1, h3]);
+    }
+
+    #[test]
+    fn softtimer_pending_sorted_unit() {
+        let timers = SofTimers::new();
+        let h200 = timers.create().unwrap();
+        let h50 = timers.create().unwrap();
+
+        assert_eq!(timers.set_unit(h200, 100), Ok(()));
+        assert_eq!(timers.start(h200, 2, false), Ok(()));
+        assert_eq!(timers.start(h50, 50, false), Ok(()));
+
+        let mut out = [0usize; MAX_SOFT_COUNTER];
+        assert_eq!(timers.pending_sorted(&mut out), 2);
+        assert_eq!(out[..2], [h50, h200]);
+
+        // 2 units with 80 of 100 ticks counted are closer than 50 ticks
+        for _ in 0..180 {
+            timers.update();
+        }
+        assert_eq!(timers.start(h50, 50, false), Ok(()));
+        assert_eq!(timers.pending_sorted(&mut out), 2);
+        assert_eq!(out[..2], [h200, h50]);
+    }
+
+    #[test]
+    fn softtimer_is_signaled() {
+        let timers = SofTimers::new();
+        let h = timers.create().unwrap();
+
+        assert_eq!(timers.start(h, 1, true), Ok(()));
+        assert_eq!(timers.is_signaled(h), Ok(false));
+        timers.update();
+        assert_eq!(timers.is_signaled(h), Ok(true));
+        assert_eq!(timers.is_signaled(h), Ok(false));
+        assert_eq!(
+            timers.is_signaled(MAX_SOFT_COUNTER),
+            Err(SoftTimerErr::InvalidParameter)
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn softtimer_wait_blocking() {
+        let timers = SofTimers::new();
+        let h = timers.create().unwrap();
+        let mut ticks = 0usize;
+
+        assert_eq!(
+            timers.wait_blocking(h, || ticks += 1),
+            Err(SoftTimerErr::Disabled)
+        );
+
+        assert_eq!(timers.start(h, 5, false), Ok(()));
+        assert_eq!(timers.wait_blocking(h, || ticks += 1), Ok(()));
+        assert_eq!(ticks, 5);
+    }
+
+    #[test]
+    fn softtimer_update_with_rearm() {
+        let timers = SofTimers::new();
+        let h1 = timers.create().unwrap();
+        let h2 = timers.create().unwrap();
+        let mut fired = 0usize;
+
+        assert_eq!(timers.start(h1, 2, false), Ok(()));
+        assert_eq!(timers.start(h2, 5, false), Ok(()));
+
+        for _ in 0..4 {
+            timers.update_with(|h| {
+                fired += 1;
+                assert_eq!(h, h1);
+                assert_eq!(timers.start(h, 2, false), Ok(()));
+            });
+        }
+
+        assert_eq!(fired, 2);
+        let data: SoftTimerData = timers.get(h1).unwrap();
+        assert_eq!(data.counter.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn softtimer_try_start() {
+        let timers = SofTimers::new();
+        let h = timers.create().unwrap();
+
+        assert_eq!(timers.try_start(h, 5, false), Ok(()));
+        timers.update();
+        assert_eq!(
+            timers.try_start(h, 10, false),
+            Err(SoftTimerErr::AlreadyRunning)
+        );
+        let data: SoftTimerData = timers.get(h).unwrap();
+        assert_eq!(data.counter.load(Ordering::Relaxed), 4);
+
+        assert_eq!(timers.start(h, 10, false), Ok(()));
+        let data: SoftTimerData = timers.get(h).unwrap();
+        assert_eq!(data.counter.load(Ordering::Relaxed), 10);
+
+        assert_eq!(timers.stop(h), Ok(()));
+        assert_eq!(timers.try_start(h, 3, false), Ok(()));
+    }
+
+    #[test]
+    fn softtimer_update_tick() {
+        let timers = SofTimers::new();
+        let h1 = timers.create().unwrap();
+        let h2 = timers.create().unwrap();
+
+        assert_eq!(timers.start(h1, 2, false), Ok(()));
+        assert_eq!(timers.start(h2, 2, false), Ok(()));
+
+        assert!(!timers.update_tick());
+        assert!(timers.update_tick());
+        assert!(!timers.update_tick());
+    }
+
+    #[test]
+    fn softtimer_update_count_expired() {
+        let timers = SofTimers::new();
+        let h1 = timers.create().unwrap();
+        let h2 = timers.create().unwrap();
+        let h3 = timers.create().unwrap();
+        let h4 = timers.create().unwrap();
+
+        assert_eq!(timers.start(h1, 3, false), Ok(()));
+        assert_eq!(timers.start(h2, 3, true), Ok(()));
+        assert_eq!(timers.start(h4, 5, false), Ok(()));
+        assert_eq!(timers.update_count_expired(), 0);
+        assert_eq!(timers.start(h3, 2, false), Ok(()));
+
+        assert_eq!(timers.update_count_expired(), 0);
+        assert_eq!(timers.update_count_expired(), 3);
+        assert_eq!(timers.update_count_expired(), 0);
+    }
+
+    #[test]
+    fn softtimer_min_interval() {
+        let timers = SofTimers::new();
+        let h = timers.create().unwrap();
+        let mut fired = [0 as Counter; 8];
+        let mut count = 0usize;
+
+        assert_eq!(timers.start(h, 2, true), Ok(()));
+        assert_eq!(timers.set_min_interval(h, 5), Ok(()));
+
+        for _ in 0..20 {
+            timers.update_with(|handle| {
+                assert_eq!(handle, h);
+                fired[count] = timers.now();
+                count += 1;
+            });
+            assert_eq!(timers.is_signaled(h), Ok(timers.now().is_multiple_of(2)));
+        }
+
+        assert_eq!(fired[..count], [2, 8, 14, 20]);
+        assert_eq!(timers.take_suppressed(h), Ok(6));
+        assert_eq!(timers.take_suppressed(h), Ok(0));
+
+        assert_eq!(timers.set_min_interval(h, 0), Ok(()));
+        assert!(!timers.update_tick());
+        assert!(timers.update_tick());
+        assert_eq!(timers.take_suppressed(h), Ok(0));
+    }
+
+    #[test]
+    fn softtimer_reserve() {
+        let timers = SofTimers::new();
+        let single = timers.create().unwrap();
+        let h = timers.create().unwrap();
+        assert_eq!(timers.delete(single), Ok(()));
+
+        let block = timers.reserve(4).unwrap();
+        assert_eq!(block, 2..6);
+        assert_eq!(timers.reserve(0), Err(SoftTimerErr::InvalidParameter));
+        assert_eq!(timers.reserve(15), Err(SoftTimerErr::LimitExceeded));
+
+        assert_eq!(timers.start(h, 3, false), Ok(()));
+        timers.update();
+        assert_eq!(timers.running_count(), 1);
+
+        for handle in block {
+            let data: SoftTimerData = timers.get(handle).unwrap();
+            assert_eq!(data.state, State::Disabled);
+            assert_eq!(data.counter.load(Ordering::Relaxed), 0);
+        }
+    }
+
+    #[test]
+    fn softtimer_next_expired() {
+        let timers = SofTimers::new();
+        let long = timers.create().unwrap();
+        let short = timers.create().unwrap();
+
+        assert_eq!(timers.next_expired(), None);
+        assert_eq!(timers.start(long, 5, true), Ok(()));
+        timers.update();
+        timers.update();
+        assert_eq!(timers.start(short, 3, true), Ok(()));
+
+        timers.update();
+        timers.update();
+        assert_eq!(timers.next_expired(), None);
+
+        timers.update();
+        assert_eq!(timers.next_expired(), Some(short));
+        assert_eq!(timers.next_expired(), Some(short));
+
+        assert_eq!(timers.is_signaled(short), Ok(true));
+        assert_eq!(timers.next_expired(), Some(long));
+    }
+
+    #[test]
+    fn softtimer_grace() {
+        let timers = SofTimers::new();
+        let one_shot = timers.create().unwrap();
+        let periodic = timers.create().unwrap();
+
+        assert_eq!(timers.start_with_grace(one_shot, 2, false, 3), Ok(()));
+        assert_eq!(timers.start_with_grace(periodic, 2, true, 3), Ok(()));
+
+        timers.update();
+        assert_eq!(timers.is_signaled(one_shot), Ok(false));
+
+        for _ in 0..3 {
+            timers.update();
+            assert_eq!(timers.is_signaled(one_shot), Ok(true));
+            assert_eq!(timers.is_signaled(periodic), Ok(true));
+        }
+
+        timers.update();
+        assert_eq!(timers.is_signaled(one_shot), Ok(false));
+        assert_eq!(timers.get(one_shot).unwrap().state, State::Disabled);
+        assert_eq!(timers.is_signaled(periodic), Ok(false));
+        let data: SoftTimerData = timers.get(periodic).unwrap();
+        assert_eq!(data.state, State::Running);
+        assert_eq!(data.counter.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn softtimer_coalesce() {
+        let timers = SofTimers::new();
+        let h10 = timers.create().unwrap();
+        let h11 = timers.create().unwrap();
+        let h12 = timers.create().unwrap();
+        let h13 = timers.create().unwrap();
+
+        timers.set_coalesce_window(2);
+        assert_eq!(timers.start(h10, 10, false), Ok(()));
+        assert_eq!(timers.start(h11, 11, false), Ok(()));
+        assert_eq!(timers.start(h12, 12, false), Ok(()));
+        assert_eq!(timers.start(h13, 13, false), Ok(()));
+
+        for _ in 0..9 {
+            assert!(!timers.update_tick());
+        }
+
+        let mut fired = 0usize;
+        timers.update_with(|_| fired += 1);
+        assert_eq!(fired, 3);
+        assert_eq!(timers.is_signaled(h10), Ok(true));
+        assert_eq!(timers.is_signaled(h11), Ok(true));
+        assert_eq!(timers.is_signaled(h12), Ok(true));
+        assert_eq!(timers.is_signaled(h13), Ok(false));
+    }
+
+    #[test]
+    fn softtimer_coalesce_unit() {
+        let timers = SofTimers::new();
+        let h1 = timers.create().unwrap();
+        let h200 = timers.create().unwrap();
+
+        timers.set_coalesce_window(2);
+        assert_eq!(timers.set_unit(h200, 100), Ok(()));
+        assert_eq!(timers.start(h1, 1, false), Ok(()));
+        assert_eq!(timers.start(h200, 2, false), Ok(()));
+
+        // 2 units of 100 ticks are outside the window of 2 ticks
+        timers.update();
+        assert_eq!(timers.is_signaled(h1), Ok(true));
+        assert_eq!(timers.is_signaled(h200), Ok(false));
+
+        // 2 units with 99 of 100 ticks counted are 101 ticks away
+        for _ in 0..97 {
+            timers.update();
+        }
+        assert_eq!(timers.start(h1, 1, false), Ok(()));
+        timers.update();
+        assert_eq!(timers.is_signaled(h200), Ok(false));
+
+        // 1 unit with 99 of 100 ticks counted is 1 tick away
+        for _ in 0..99 {
+            timers.update();
+        }
+        assert_eq!(timers.start(h1, 1, false), Ok(()));
+        timers.update();
+        assert_eq!(timers.is_signaled(h200), Ok(true));
+    }
+
+    #[test]
+    fn softtimer_restart_aligned() {
+        let timers = SofTimers::new();
+        let h1 = timers.create().unwrap();
+        let h2 = timers.create().unwrap();
+
+        assert_eq!(timers.start(h1, 10, false), Ok(()));
+        assert_eq!(timers.start(h2, 10, false), Ok(()));
+        for _ in 0..3 {
+            timers.update();
+        }
+        assert_eq!(timers.restart_aligned(h1), Ok(()));
+        for _ in 0..4 {
+            timers.update();
+        }
+        assert_eq!(timers.restart_aligned(h2), Ok(()));
+
+        for _ in 0..2 {
+            assert!(!timers.update_tick());
+        }
+        let mut fired = 0usize;
+        timers.update_with(|_| fired += 1);
+        assert_eq!(fired, 2);
+        assert_eq!(timers.now(), 10);
+    }
+
+    #[test]
+    fn softtimer_update_elapsed() {
+        let timers = SofTimers::new();
+        let h = timers.create().unwrap();
+        let mut total = 0usize;
+        let mut expiries = 0usize;
+
+        assert_eq!(timers.start_millis(h, 10, 1000, true), Ok(()));
+        for delta in [3usize, 1, 7, 2, 9, 4, 6, 5, 8].iter().cycle().take(900) {
+            assert_eq!(timers.update_elapsed(*delta), Ok(()));
+            total += delta;
+            if timers.is_signaled(h) == Ok(true) {
+                expiries += 1;
+            }
+        }
+
+        assert_eq!(timers.now(), total);
+        assert_eq!(expiries, total / 10);
+    }
+
+    #[test]
+    fn softtimer_find_by_tag() {
+        let timers = SofTimers::new();
+        let h1 = timers.create().unwrap();
+        let h2 = timers.create().unwrap();
+        let h3 = timers.create().unwrap();
+
+        assert_eq!(timers.find_by_tag(7), None);
+        assert_eq!(timers.start_tagged(h1, 5, false, 7), Ok(()));
+        assert_eq!(timers.start_tagged(h2, 5, false, 9), Ok(()));
+        assert_eq!(timers.start_tagged(h3, 5, false, 7), Ok(()));
+        assert_eq!(timers.find_by_tag(7), Some(h1));
+        assert_eq!(timers.find_by_tag(9), Some(h2));
+        assert_eq!(timers.find_by_tag(1), None);
+
+        assert_eq!(timers.start(h1, 3, false), Ok(()));
+        assert_eq!(timers.find_by_tag(7), Some(h1));
+        assert_eq!(timers.delete(h1), Ok(()));
+        assert_eq!(timers.find_by_tag(7), Some(h3));
+    }
+
+    #[test]
+    fn softtimer_timeout() {
+        let timers = SofTimers::new();
+        let h = timers.create().unwrap();
+        let timeout = timers.timeout(h, 3).unwrap();
+
+        assert_eq!(timeout.handle(), h);
+        for _ in 0..3 {
+            assert!(!timeout.is_elapsed());
+            timers.update();
+        }
+        assert!(timeout.is_elapsed());
+        assert!(timeout.is_elapsed());
+
+        timeout.reset();
+        assert!(!timeout.is_elapsed());
+        for _ in 0..3 {
+            timers.update();
+        }
+        assert!(timeout.is_elapsed());
+
+        assert!(timers.timeout(h + 1, 3).is_err());
+    }
+
+    #[test]
+    fn softtimer_next_wakeup() {
+        let timers = SofTimers::new();
+        let one_shot = timers.create().unwrap();
+        let graced = timers.create().unwrap();
+        let stopped = timers.create().unwrap();
+
+        assert_eq!(timers.next_wakeup(), None);
+        assert_eq!(timers.start(one_shot, 2, false), Ok(()));
+        assert_eq!(timers.start_with_grace(graced, 4, true, 3), Ok(()));
+        assert_eq!(timers.start(stopped, 1, false), Ok(()));
+        assert_eq!(timers.stop(stopped), Ok(()));
+        assert_eq!(timers.next_wakeup(), Some(2));
+
+        timers.update();
+        timers.update();
+        assert_eq!(timers.next_wakeup(), Some(2));
+
+        timers.update();
+        timers.update();
+        assert_eq!(timers.is_signaled(graced), Ok(true));
+        assert_eq!(timers.next_wakeup(), Some(3));
+
+        for _ in 0..3 {
+            timers.update();
+        }
+        assert_eq!(timers.next_wakeup(), Some(4));
+        assert_eq!(timers.delete(graced), Ok(()));
+        assert_eq!(timers.next_wakeup(), None);
+    }
+
+    #[test]
+    fn softtimer_freeze() {
+        let timers = SofTimers::new();
+        let t0 = timers.create().unwrap();
+        let t1 = timers.create().unwrap();
+        timers.start(t0, 3, false).unwrap();
+        timers.start(t1, 5, true).unwrap();
+        timers.update();
+
+        timers.freeze();
+        assert!(timers.is_frozen());
+        for _ in 0..10 {
+            timers.update();
+        }
+        assert_eq!(timers.update_elapsed(10), Ok(()));
+        timers.advance_to(100, |_| panic!("frozen pool expired"));
+        assert_eq!(timers.now(), 1);
+        assert_eq!(timers.get(t0).unwrap().counter.load(Ordering::Relaxed), 2);
+        assert_eq!(timers.get(t1).unwrap().counter.load(Ordering::Relaxed), 4);
+        assert_eq!(timers.is_signaled(t0), Ok(false));
+
+        timers.unfreeze();
+        assert!(!timers.is_frozen());
+        timers.update();
+        assert_eq!(timers.get(t0).unwrap().counter.load(Ordering::Relaxed), 1);
+        assert_eq!(timers.get(t1).unwrap().counter.load(Ordering::Relaxed), 3);
+        timers.update();
+        assert_eq!(timers.is_signaled(t0), Ok(true));
+    }
+
+    #[test]
+    fn softtimer_uptime() {
+        let timers = SofTimers::new();
+        assert_eq!(timers.uptime_ticks(), 0);
+
+        for _ in 0..250 {
+            timers.update();
+        }
+        assert_eq!(timers.update_elapsed(1250), Ok(()));
+        assert_eq!(timers.uptime_ticks(), 1500);
+        assert_eq!(timers.uptime_millis(1000), 1500);
+        assert_eq!(timers.uptime_millis(400), 3750);
+        assert_eq!(timers.uptime_millis(7), 214_285);
+        assert_eq!(timers.uptime_millis(0), 0);
+    }
+
+    #[test]
+    fn softtimer_unit() {
+        let timers = SofTimers::new();
+        let h = timers.create().unwrap();
+        let advanced = timers.create().unwrap();
+
+        assert_eq!(timers.set_unit(h, 0), Err(SoftTimerErr::InvalidParameter));
+        assert_eq!(timers.set_unit(h, 100), Ok(()));
+        assert_eq!(timers.start(h, 3, false), Ok(()));
+        assert_eq!(timers.next_wakeup(), Some(300));
+
+        for _ in 0..299 {
+            timers.update();
+        }
+        assert_eq!(timers.is_signaled(h), Ok(false));
+        assert_eq!(timers.next_wakeup(), Some(1));
+        timers.update();
+        assert_eq!(timers.is_signaled(h), Ok(true));
+        assert_eq!(timers.expired_at(h), Ok(Some(300)));
+
+        assert_eq!(timers.set_unit(advanced, 100), Ok(()));
+        assert_eq!(timers.start(advanced, 3, false), Ok(()));
+        timers.advance_to(timers.now() + 150, |_| ());
+        assert_eq!(timers.next_wakeup(), Some(150));
+        let mut expired = None;
+        timers.advance_to(timers.now() + 200, |handle| expired = Some(handle));
+        assert_eq!(expired, Some(advanced));
+        assert_eq!(timers.expired_at(advanced), Ok(Some(600)));
     }
 
     #[test]
-    fn softimer_modify() {
+    fn softtimer_remaining_subticks() {
+        let timers = SofTimers::new();
+        let h = timers.create().unwrap();
+
+        assert_eq!(timers.set_unit(h, 100), Ok(()));
+        assert_eq!(timers.start(h, 5, false), Ok(()));
+        assert_eq!(timers.remaining_subticks(h), Ok((5, 0)));
+
+        for _ in 0..250 {
+            timers.update();
+        }
+        assert_eq!(timers.remaining_subticks(h), Ok((3, 50)));
+
+        assert_eq!(timers.update_elapsed(49), Ok(()));
+        assert_eq!(timers.remaining_subticks(h), Ok((3, 99)));
+        timers.update();
+        assert_eq!(timers.remaining_subticks(h), Ok((2, 0)));
+    }
+
+    #[test]
+    fn softtimer_check_and_count_expiries() {
+        let timers = SofTimers::new();
+        let periodic = timers.create().unwrap();
+        let one_shot = timers.create().unwrap();
+
+        assert_eq!(timers.start(periodic, 10, true), Ok(()));
+        assert_eq!(timers.start(one_shot, 10, false), Ok(()));
+        for _ in 0..9 {
+            timers.update();
+        }
+        assert_eq!(timers.check_and_count_expiries(periodic), Ok(0));
+
+        for _ in 0..36 {
+            timers.update();
+        }
+        assert_eq!(timers.check_and_count_expiries(periodic), Ok(4));
+        assert_eq!(timers.check_and_count_expiries(periodic), Ok(0));
+        assert_eq!(timers.check_and_count_expiries(one_shot), Ok(1));
+        assert_eq!(timers.check_and_count_expiries(one_shot), Ok(1));
+
+        for _ in 0..5 {
+            timers.update();
+        }
+        assert_eq!(timers.check_and_count_expiries(periodic), Ok(1));
+
+        timers.advance_to(timers.now() + 25, |_| ());
+        assert_eq!(timers.check_and_count_expiries(periodic), Ok(2));
+        timers.advance_to(timers.now() + 5, |_| ());
+        assert_eq!(timers.check_and_count_expiries(periodic), Ok(1));
+        assert_eq!(
+            timers.check_and_count_expiries(one_shot + 2),
+            Err(SoftTimerErr::NoSuchTimer)
+        );
+    }
+
+    #[test]
+    fn softtimer_warning() {
+        let timers = SofTimers::new();
+        let h = timers.create().unwrap();
+
+        assert_eq!(timers.set_warn_at(h, 3), Ok(()));
+        assert_eq!(timers.is_warning(h), Ok(false));
+        assert_eq!(timers.start(h, 10, false), Ok(()));
+
+        for tick in 1..=10 {
+            assert_eq!(timers.is_warning(h), Ok(tick > 7), "tick {}", tick);
+            timers.update();
+        }
+        assert_eq!(timers.is_signaled(h), Ok(true));
+        assert_eq!(timers.is_warning(h), Ok(false));
+
+        assert_eq!(timers.set_warn_at(h, 0), Ok(()));
+        assert_eq!(timers.start(h, 2, false), Ok(()));
+        timers.update();
+        assert_eq!(timers.is_warning(h), Ok(false));
+        assert_eq!(timers.is_warning(h + 1), Err(SoftTimerErr::NoSuchTimer));
+    }
+
+    #[test]
+    fn softtimer_any_running() {
+        let timers = SofTimers::new();
+        assert!(!timers.any_running());
+
+        let h = timers.create().unwrap();
+        assert!(!timers.any_running());
+        assert_eq!(timers.start(h, 2, false), Ok(()));
+        assert!(timers.any_running());
+        assert_eq!(timers.stop(h), Ok(()));
+        assert!(!timers.any_running());
+    }
+
+    #[test]
+    fn softtimer_longest_running() {
+        let timers = SofTimers::new();
+        assert_eq!(timers.longest_running(), None);
+
+        let long = timers.create().unwrap();
+        let short = timers.create().unwrap();
+        let periodic = timers.create().unwrap();
+        let stopped = timers.create().unwrap();
+        timers.start(stopped, 100, false).unwrap();
+        assert_eq!(timers.update_elapsed(50), Ok(()));
+        timers.stop(stopped).unwrap();
+
+        timers.start(long, 10, false).unwrap();
+        assert_eq!(timers.update_elapsed(4), Ok(()));
+        timers.start(short, 10, false).unwrap();
+        timers.start(periodic, 3, true).unwrap();
+        assert_eq!(timers.update_elapsed(2), Ok(()));
+        assert_eq!(timers.longest_running(), Some(long));
+
+        // periodic expired 3 ticks after its start and is not restarted
+        assert_eq!(timers.update_elapsed(9), Ok(()));
+        assert_eq!(timers.longest_running(), Some(periodic));
+
+        assert_eq!(timers.is_signaled(periodic), Ok(true));
+        assert_eq!(timers.longest_running(), Some(long));
+    }
+
+    #[test]
+    fn softtimer_peak_running() {
+        let timers = SofTimers::new();
+        let h1 = timers.create().unwrap();
+        let h2 = timers.create().unwrap();
+        let h3 = timers.create().unwrap();
+        assert_eq!(timers.peak_running(), 0);
+
+        timers.start(h1, 5, false).unwrap();
+        timers.start(h2, 5, false).unwrap();
+        timers.stop(h1).unwrap();
+        timers.start(h3, 5, false).unwrap();
+        assert_eq!(timers.peak_running(), 2);
+
+        timers.restart(h1).unwrap();
+        assert_eq!(timers.peak_running(), 3);
+        timers.stop(h1).unwrap();
+        timers.stop(h2).unwrap();
+        timers.start(h2, 5, false).unwrap();
+        assert_eq!(timers.peak_running(), 3);
+
+        timers.reset_peak();
+        assert_eq!(timers.peak_running(), 2);
+        timers.stop(h2).unwrap();
+        timers.stop(h3).unwrap();
+        timers.reset_peak();
+        assert_eq!(timers.peak_running(), 0);
+        timers.restart_aligned(h3).unwrap();
+        assert_eq!(timers.peak_running(), 1);
+    }
+
+    #[test]
+    fn softtimer_restart_gate() {
+        static CHECKS: AtomicUsize = AtomicUsize::new(0);
+
+        let timers = SofTimers::new();
+        let h = timers.create().unwrap();
+        let mut expiries = 0usize;
+
+        assert_eq!(timers.start(h, 2, true), Ok(()));
+        assert_eq!(
+            timers.set_restart_gate(h, Some(|_| CHECKS.fetch_add(1, Ordering::Relaxed) < 2)),
+            Ok(())
+        );
+
+        for _ in 0..20 {
+            timers.update_with(|_| expiries += 1);
+            let _ = timers.is_signaled(h);
+        }
+
+        assert_eq!(expiries, 3);
+        assert_eq!(CHECKS.load(Ordering::Relaxed), 3);
+        assert_eq!(timers.expired_at(h), Ok(Some(6)));
+        assert_eq!(timers.is_signaled(h), Ok(true));
+    }
+
+    #[test]
+    fn softtimer_expired_latch() {
+        let timers = SofTimers::new();
+        let h = timers.create().unwrap();
+
+        assert_eq!(timers.start(h, 3, true), Ok(()));
+        assert_eq!(timers.update_elapsed(2), Ok(()));
+        assert_eq!(timers.expired_latch(h), Ok(false));
+
+        timers.update();
+        assert_eq!(timers.is_signaled(h), Ok(true));
+        assert_eq!(timers.is_signaled(h), Ok(false));
+        for _ in 0..100 {
+            timers.update();
+            let _ = timers.is_signaled(h);
+            assert_eq!(timers.expired_latch(h), Ok(true));
+        }
+
+        assert_eq!(timers.clear_latch(h), Ok(()));
+        assert_eq!(timers.expired_latch(h), Ok(false));
+        assert_eq!(timers.restart(h), Ok(()));
+        timers.advance_to(timers.now() + 3, |_| ());
+        assert_eq!(timers.expired_latch(h), Ok(true));
+    }
+
+    #[test]
+    fn softtimer_expiry_action() {
+        let actions = AtomicUsize::new(0);
+        let mut expired = None;
+        let mut action = |handle| {
+            actions.fetch_add(1, Ordering::Relaxed);
+            expired = Some(handle);
+        };
+
+        let timers = SofTimers::new();
+        let watchdog = timers.create().unwrap();
+        let periodic = timers.create().unwrap();
+
+        timers.set_expiry_action(Some(&mut action));
+        assert_eq!(timers.start(watchdog, 3, false), Ok(()));
+        assert_eq!(timers.start(periodic, 2, true), Ok(()));
+
+        for _ in 0..4 {
+            timers.update();
+            timers.update();
+            assert_eq!(timers.restart(watchdog), Ok(()));
+        }
+        assert_eq!(actions.load(Ordering::Relaxed), 0);
+
+        for _ in 0..5 {
+            timers.update();
+        }
+        assert_eq!(actions.load(Ordering::Relaxed), 1);
+
+        timers.set_expiry_action(None);
+        assert_eq!(timers.restart(watchdog), Ok(()));
+        for _ in 0..3 {
+            timers.update();
+        }
+        assert_eq!(actions.load(Ordering::Relaxed), 1);
+        assert_eq!(expired, Some(watchdog));
+    }
+
+    #[test]
+    fn softtimer_diff() {
+        let timers = SofTimers::new();
+        let h1 = timers.create().unwrap();
+        let h2 = timers.create().unwrap();
+        let h3 = timers.create().unwrap();
+
+        assert_eq!(timers.start(h1, 5, false), Ok(()));
+        assert_eq!(timers.start(h3, 5, false), Ok(()));
+        assert_eq!(timers.start(h2, 5, false), Ok(()));
+        assert_eq!(timers.stop(h2), Ok(()));
+
+        let before = timers.snapshot_all();
+        timers.update();
+        let after = timers.snapshot_all();
+
+        let mut out = [0usize; MAX_SOFT_COUNTER];
+        assert_eq!(SofTimers::diff(&before, &after, &mut out), 2);
+        assert_eq!(out[..2], [h1, h3]);
+        assert_eq!(after[h1].unwrap().counter, 4);
+
+        assert_eq!(SofTimers::diff(&after, &after, &mut out), 0);
+    }
+
+    #[test]
+    fn softtimer_stale_handle() {
+        let timers = SofTimers::new();
+        let old = timers.create().unwrap();
+
+        assert_eq!(timers.delete(old), Ok(()));
+        assert_eq!(timers.start(old, 5, false), Err(SoftTimerErr::NoSuchTimer));
+
+        let new = timers.create().unwrap();
+        assert_ne!(new, old);
+        assert_eq!(timers.start(old, 5, false), Err(SoftTimerErr::StaleHandle));
+        assert_eq!(timers.delete(old), Err(SoftTimerErr::StaleHandle));
+        assert_eq!(timers.is_signaled(old), Err(SoftTimerErr::StaleHandle));
+        assert_eq!(timers.get(old).err(), Some(SoftTimerErr::StaleHandle));
+
+        assert_eq!(timers.start(new, 1, false), Ok(()));
+        let mut fired = [0usize; 1];
+        timers.update_with(|h| fired[0] = h);
+        assert_eq!(fired[0], new);
+        assert_eq!(timers.is_signaled(new), Ok(true));
+    }
+
+    #[test]
+    fn softtimer_start_millis_overflow() {
         let timers = SofTimers::new();
         let h = timers.create().unwrap();
+
+        assert_eq!(timers.start_millis(h, 15, 100, false), Ok(()));
         let data: SoftTimerData = timers.get(h).unwrap();
+        assert_eq!(data.counter.load(Ordering::Relaxed), 2);
 
-        assert_eq!(data.state, State::Disabled);
+        let max_ms = Counter::MAX / 1000;
+        assert_eq!(timers.start_millis(h, max_ms, 1000, false), Ok(()));
+        assert_eq!(
+            timers.start_millis(h, max_ms + 1, 1000, false),
+            Err(SoftTimerErr::LimitExceeded)
+        );
+        assert_eq!(
+            timers.start_millis(h, 1, 0, false),
+            Err(SoftTimerErr::InvalidParameter)
+        );
 
-        assert_eq!(timers.start(h, 1234, true), Ok(()));
         let data: SoftTimerData = timers.get(h).unwrap();
+        assert_eq!(data.counter.load(Ordering::Relaxed), max_ms);
+    }
+
+    #[test]
+    fn softtimer_update_by_overflow() {
+        let timers: SofTimers<u16> = SofTimers::with_counter();
+        let h = timers.create().unwrap();
+
+        // 30000 ms * 2000 Hz overflows u16 in between, but not the result
+        assert_eq!(timers.start_millis(h, 30_000, 2000, false), Ok(()));
+        assert_eq!(
+            timers.start_millis(h, 40_000, 2000, false),
+            Err(SoftTimerErr::LimitExceeded)
+        );
+
+        assert_eq!(timers.start(h, 10, true), Ok(()));
+        assert_eq!(timers.update_by(u16::MAX), Ok(()));
+        assert_eq!(timers.now(), u16::MAX);
+
+        // further ticks would overflow the ticks counted since the expiry
+        assert_eq!(timers.update_by(11), Err(SoftTimerErr::LimitExceeded));
+        assert_eq!(timers.now(), u16::MAX);
+        assert_eq!(timers.check_and_count_expiries(h), Ok(6553));
+
+        assert_eq!(timers.update_by(10), Ok(()));
+        assert_eq!(timers.check_and_count_expiries(h), Ok(1));
+    }
+
+    #[test]
+    fn softtimer_update_by_overflow_u64() {
+        let timers: SofTimers<u64> = SofTimers::with_counter();
+        let h = timers.create().unwrap();
+
+        assert_eq!(timers.start(h, 1, true), Ok(()));
+        assert_eq!(timers.update_by(1), Ok(()));
+        assert_eq!(timers.update_by(u64::MAX - 1), Ok(()));
+        assert_eq!(timers.update_by(2), Err(SoftTimerErr::LimitExceeded));
+        assert_eq!(timers.now(), u64::MAX);
+    }
+
+    #[test]
+    fn softtimer_counter_width() {
+        let narrow: SofTimers<u16> = SofTimers::with_counter();
+        let wide: SofTimers<u32> = SofTimers::with_counter();
+        let hn = narrow.create().unwrap();
+        let hw = wide.create().unwrap();
+
+        assert_eq!(narrow.start_millis(hn, 60_000, 1000, false), Ok(()));
+        assert_eq!(
+            narrow.start_millis(hn, 60_000, 2000, false),
+            Err(SoftTimerErr::LimitExceeded)
+        );
+        assert_eq!(wide.start_millis(hw, 60_000, 2000, false), Ok(()));
+        assert_eq!(
+            wide.get(hw).unwrap().counter.load(Ordering::Relaxed),
+            120_000
+        );
+
+        narrow.advance_to(u16::MAX - 2, |_| ());
+        wide.advance_to(u32::from(u16::MAX) - 2, |_| ());
+        assert_eq!(narrow.start(hn, 5, false), Ok(()));
+        assert_eq!(wide.start(hw, 5, false), Ok(()));
+        for _ in 0..5 {
+            narrow.update();
+            wide.update();
+        }
+
+        assert_eq!(narrow.now(), 2);
+        assert_eq!(wide.now(), 65_538);
+        assert_eq!(narrow.uptime_ticks(), 65_538);
+        assert_eq!(narrow.expired_at(hn), Ok(Some(2)));
+        assert_eq!(wide.expired_at(hw), Ok(Some(65_538)));
+        assert_eq!(narrow.is_signaled(hn), Ok(true));
+        assert_eq!(wide.is_signaled(hw), Ok(true));
+
+        let mut out = [0u8; 1 + RECORD_SIZE];
+        assert_eq!(wide.start(hw, 70_000, false), Ok(()));
+        assert_eq!(wide.serialize(&mut out), Ok(out.len()));
+        assert_eq!(
+            narrow.deserialize(&out),
+            Err(SoftTimerErr::InvalidParameter)
+        );
+    }
+
+    #[test]
+    fn softtimer_expired_at() {
+        let timers = SofTimers::new();
+        let h = timers.create().unwrap();
+
+        timers.update();
+        assert_eq!(timers.start(h, 3, false), Ok(()));
+        assert_eq!(timers.expired_at(h), Ok(None));
+
+        for _ in 0..5 {
+            timers.update();
+        }
+        assert_eq!(timers.expired_at(h), Ok(Some(4)));
+        assert_eq!(timers.now() - timers.expired_at(h).unwrap().unwrap(), 2);
+
+        assert_eq!(timers.restart(h), Ok(()));
+        assert_eq!(timers.expired_at(h), Ok(None));
+    }
+
+    #[test]
+    fn softtimer_chain() {
+        let timers = SofTimers::new();
+        let a = timers.create().unwrap();
+        let b = timers.create().unwrap();
+
+        assert_eq!(timers.start(b, 4, false), Ok(()));
+        assert_eq!(timers.stop(b), Ok(()));
+        assert_eq!(timers.start(a, 2, false), Ok(()));
+        assert_eq!(timers.chain(a, b), Ok(()));
+        assert_eq!(
+            timers.chain(a, MAX_SOFT_COUNTER),
+            Err(SoftTimerErr::InvalidParameter)
+        );
 
+        timers.update();
+        assert_eq!(timers.get(b).unwrap().state, State::Stopped);
+
+        timers.update();
+        assert_eq!(timers.is_signaled(a), Ok(true));
+        let data: SoftTimerData = timers.get(b).unwrap();
         assert_eq!(data.state, State::Running);
-        assert_eq!(data.auto_restart, true);
-        assert_eq!(data.counter.load(Ordering::Relaxed), 1234);
+        assert_eq!(data.counter.load(Ordering::Relaxed), 4);
+    }
+
+    #[test]
+    fn softtimer_count_by_state() {
+        let timers = SofTimers::new();
+        assert_eq!(timers.count_by_state(), [0, 0, 0]);
+
+        let _disabled = timers.create().unwrap();
+        let stopped = timers.create().unwrap();
+        let running1 = timers.create().unwrap();
+        let running2 = timers.create().unwrap();
+
+        assert_eq!(timers.start(stopped, 5, false), Ok(()));
+        assert_eq!(timers.stop(stopped), Ok(()));
+        assert_eq!(timers.start(running1, 5, false), Ok(()));
+        assert_eq!(timers.start(running2, 5, false), Ok(()));
+
+        let counts = timers.count_by_state();
+        assert_eq!(counts, [1, 1, 2]);
+        assert_eq!(counts[State::Running as usize], timers.running_count());
+    }
 
+    #[test]
+    fn softtimer_start_immediate() {
+        let timers = SofTimers::new();
+        let h = timers.create().unwrap();
+
+        assert_eq!(timers.start_immediate(h, 3, true), Ok(()));
+        let mut signals = [false; 7];
+        for signal in signals.iter_mut() {
+            timers.update();
+            *signal = timers.is_signaled(h).unwrap();
+        }
+        assert_eq!(signals, [true, false, false, true, false, false, true]);
+    }
+
+    #[test]
+    fn softtimer_invariants() {
+        let timers = SofTimers::new();
+        let h = timers.create().unwrap();
+
+        assert_eq!(timers.start_with_grace(h, 2, false, 2), Ok(()));
+        for _ in 0..5 {
+            timers.update();
+        }
+        assert_eq!(timers.get(h).unwrap().state, State::Disabled);
+
+        assert_eq!(timers.start(h, 5, true), Ok(()));
+        timers.update();
         assert_eq!(timers.stop(h), Ok(()));
+        assert_eq!(timers.restart(h), Ok(()));
+        assert_eq!(timers.disable(h), Ok(()));
 
         let data: SoftTimerData = timers.get(h).unwrap();
-        assert_eq!(data.state, State::Stopped);
-        assert_eq!(data.auto_restart, true);
-        assert_eq!(data.counter.load(Ordering::Relaxed), 1234);
+        assert_eq!(data.counter.load(Ordering::Relaxed), 0);
+        data.check_invariants();
+    }
 
-        assert_eq!(timers.delete(h), Ok(()));
+    #[test]
+    fn softtimer_iter_remaining() {
+        let timers = SofTimers::new();
+        let h7 = timers.create().unwrap();
+        let h3 = timers.create().unwrap();
+        let idle = timers.create().unwrap();
+
+        assert_eq!(timers.start(h7, 7, false), Ok(()));
+        assert_eq!(timers.start(h3, 3, false), Ok(()));
+        timers.update();
+
+        let mut pairs = [(0usize, 0usize); MAX_SOFT_COUNTER];
+        let mut count = 0usize;
+        for (pair, entry) in timers.iter_remaining().zip(pairs.iter_mut()) {
+            *entry = pair;
+            count += 1;
+        }
+        assert_eq!(pairs[..count], [(h7, 6), (h3, 2), (idle, 0)]);
+
+        let soonest = timers
+            .iter_remaining()
+            .filter(|&(_, counter)| 0 < counter)
+            .min_by_key(|&(_, counter)| counter);
+        assert_eq!(soonest, Some((h3, 2)));
     }
+
     #[test]
-    fn softtimer_disable() {
+    fn softtimer_reconfigure() {
         let timers = SofTimers::new();
         let h = timers.create().unwrap();
-        assert_eq!(timers.disable(h), Ok(()));
+
+        assert_eq!(timers.reconfigure(h, 10, true), Ok(0));
+        timers.update();
+        assert_eq!(timers.reconfigure(h, 20, true), Ok(10));
+        assert_eq!(timers.reconfigure(h, 5, false), Ok(20));
+
         let data: SoftTimerData = timers.get(h).unwrap();
-        assert_eq!(data.state, State::Disabled);
+        assert_eq!(data.counter.load(Ordering::Relaxed), 5);
+        assert!(!data.auto_restart);
+    }
+
+    /// Deterministic xorshift generator for the randomized tests.
+    #[cfg(feature = "std")]
+    struct Rng(u32);
+
+    #[cfg(feature = "std")]
+    impl Rng {
+        fn next(&mut self, bound: usize) -> usize {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 17;
+            self.0 ^= self.0 << 5;
+            self.0 as usize % bound
+        }
     }
 
+    /// Applies random operations to a pool and checks after each one:
+    /// * the counter never exceeds the threshold (see check_invariants())
+    /// * a disabled or stopped timer never signals
+    /// * a running timer signals exactly if its counter is zero
+    /// * handles of deleted timers are rejected
+    #[cfg(feature = "std")]
     #[test]
-    fn softtimer_update() {
+    fn softtimer_random_operations() {
         let timers = SofTimers::new();
-        let h1 = timers.create().unwrap();
-        let h2 = timers.create().unwrap();
+        let mut handles: [Option<SoftTimerHandle>; 8] = [None; 8];
+        let mut deleted: Option<SoftTimerHandle> = None;
+        let mut rng = Rng(0x1234_5678);
+
+        for _ in 0..10_000 {
+            let slot = rng.next(handles.len());
+            let threshold = rng.next(6);
+            let auto_restart = 0 == rng.next(2);
+
+            match (handles[slot], rng.next(10)) {
+                (None, _) => handles[slot] = timers.create().ok(),
+                (Some(h), 0) => {
+                    assert_eq!(timers.delete(h), Ok(()));
+                    handles[slot] = None;
+                    deleted = Some(h);
+                }
+                (Some(h), 1) => assert_eq!(timers.start(h, threshold, auto_restart), Ok(())),
+                (Some(h), 2) => assert_eq!(
+                    timers.start_with_grace(h, threshold, auto_restart, rng.next(3)),
+                    Ok(())
+                ),
+                (Some(h), 3) => assert_eq!(timers.stop(h), Ok(())),
+                (Some(h), 4) => assert_eq!(timers.restart(h), Ok(())),
+                (Some(h), 5) => assert_eq!(timers.disable(h), Ok(())),
+                (Some(h), 6) => assert!(timers.is_signaled(h).is_ok()),
+                (Some(h), 7) => assert!(timers.start_immediate(h, threshold, auto_restart).is_ok()),
+                (Some(_), _) => timers.update(),
+            }
 
-        assert_eq!(timers.start(h1, 3, true), Ok(()));
-        assert_eq!(timers.start(h2, 1, true), Ok(()));
+            for h in handles.iter().flatten() {
+                let data: SoftTimerData = timers.get(*h).unwrap();
+                data.check_invariants();
 
-        let data: SoftTimerData = timers.get(h1).unwrap();
-        assert_eq!(data.counter.load(Ordering::Relaxed), 3);
-        let data: SoftTimerData = timers.get(h2).unwrap();
-        assert_eq!(data.counter.load(Ordering::Relaxed), 1);
+                let counter = data.counter.load(Ordering::Relaxed);
+                let signaled = matches!(data.get_signal_state(), SignalState::Signaled);
+                assert_eq!(signaled, (State::Running == data.state) && (0 == counter));
+            }
+
+            if let Some(h) = deleted.filter(|h| !handles.contains(&Some(*h))) {
+                assert!(timers.start(h, 1, false).is_err());
+            }
+        }
+    }
+
+    #[test]
+    fn softtimer_set_counter() {
+        let timers = SofTimers::new();
+        let h = timers.create().unwrap();
 
+        assert_eq!(
+            timers.set_counter(h, 1),
+            Err(SoftTimerErr::InvalidParameter)
+        );
+        assert_eq!(timers.start(h, 10, false), Ok(()));
         timers.update();
-        let data: SoftTimerData = timers.get(h1).unwrap();
-        assert_eq!(data.counter.load(Ordering::Relaxed), 2);
-        let data: SoftTimerData = timers.get(h2).unwrap();
-        assert_eq!(data.counter.load(Ordering::Relaxed), 0);
+        assert_eq!(
+            timers.set_counter(h, 11),
+            Err(SoftTimerErr::InvalidParameter)
+        );
+        assert_eq!(timers.set_counter(h, 3), Ok(()));
 
         timers.update();
-        let data: SoftTimerData = timers.get(h1).unwrap();
-        assert_eq!(data.counter.load(Ordering::Relaxed), 1);
-        let data: SoftTimerData = timers.get(h2).unwrap();
-        assert_eq!(data.counter.load(Ordering::Relaxed), 0);
+        timers.update();
+        assert_eq!(timers.is_signaled(h), Ok(false));
+        timers.update();
+        assert_eq!(timers.is_signaled(h), Ok(true));
+        assert_eq!(timers.get(h).unwrap().threshold, 10);
+    }
+
+    #[test]
+    fn softtimer_serialize() {
+        let timers = SofTimers::new();
+        let h1 = timers.create().unwrap();
+        let h2 = timers.create().unwrap();
+        let h3 = timers.create().unwrap();
+        let gap = timers.create().unwrap();
+        assert_eq!(timers.delete(gap), Ok(()));
 
+        assert_eq!(timers.start(h1, 100, true), Ok(()));
+        assert_eq!(timers.start_with_grace(h2, 7, false, 2), Ok(()));
+        assert_eq!(timers.stop(h2), Ok(()));
         timers.update();
-        let data: SoftTimerData = timers.get(h1).unwrap();
-        assert_eq!(data.counter.load(Ordering::Relaxed), 0);
-        let data: SoftTimerData = timers.get(h2).unwrap();
-        assert_eq!(data.counter.load(Ordering::Relaxed), 0);
 
-        assert_eq!(timers.restart(h1), Ok(()));
-        assert_eq!(timers.restart(h2), Ok(()));
+        let mut buf = [0u8; 128];
+        assert_eq!(
+            timers.serialize(&mut buf[..10]),
+            Err(SoftTimerErr::LimitExceeded)
+        );
+        let size = timers.serialize(&mut buf).unwrap();
+        assert_eq!(size, 1 + 3 * RECORD_SIZE);
+
+        let restored = SofTimers::new();
+        let old = restored.create().unwrap();
+        assert_eq!(restored.deserialize(&buf[..size]), Ok(()));
+        assert_eq!(
+            restored.start(old, 1, false),
+            Err(SoftTimerErr::StaleHandle)
+        );
+
+        let before = timers.snapshot_all();
+        let after = restored.snapshot_all();
+        for (before, after) in before.iter().zip(after.iter()) {
+            assert_eq!(
+                before.map(|s| (s.state, s.counter, s.threshold)),
+                after.map(|s| (s.state, s.counter, s.threshold))
+            );
+        }
+        assert_eq!(restored.get(h3).unwrap().state, State::Disabled);
+        assert_eq!(restored.get(h2).unwrap().grace, 2);
+
+        assert_eq!(
+            restored.deserialize(&buf[..size - 1]),
+            Err(SoftTimerErr::InvalidParameter)
+        );
+        buf[2] = 7;
+        assert_eq!(
+            restored.deserialize(&buf[..size]),
+            Err(SoftTimerErr::InvalidParameter)
+        );
+        assert_eq!(restored.running_count(), 1);
+    }
 
-        let data: SoftTimerData = timers.get(h1).unwrap();
-        assert_eq!(data.counter.load(Ordering::Relaxed), 3);
-        let data: SoftTimerData = timers.get(h2).unwrap();
-        assert_eq!(data.counter.load(Ordering::Relaxed), 1);
+    #[test]
+    fn softtimer_serialize_config() {
+        let timers = SofTimers::new();
+        let h = timers.create().unwrap();
+        assert_eq!(timers.set_unit(h, 100), Ok(()));
+        assert_eq!(timers.start_tagged(h, 3, false, 42), Ok(()));
+        assert_eq!(timers.set_warn_at(h, 1), Ok(()));
+        assert_eq!(timers.set_min_interval(h, 5), Ok(()));
+        timers.update();
+
+        let mut buf = [0u8; 1 + RECORD_SIZE];
+        assert_eq!(timers.serialize(&mut buf), Ok(buf.len()));
+
+        let restored = SofTimers::new();
+        assert_eq!(restored.deserialize(&buf), Ok(()));
+        assert_eq!(restored.find_by_tag(42), Some(h));
+        assert_eq!(restored.remaining_subticks(h), Ok((3, 1)));
+        assert_eq!(restored.get(h).unwrap().warn_at, 1);
+        assert_eq!(restored.get(h).unwrap().min_interval, 5);
+        for _ in 0..298 {
+            restored.update();
+        }
+        assert_eq!(restored.is_signaled(h), Ok(false));
+        restored.update();
+        assert_eq!(restored.is_signaled(h), Ok(true));
+
+        assert_eq!(timers.set_restart_gate(h, Some(|_| true)), Ok(()));
+        assert_eq!(
+            timers.serialize(&mut buf),
+            Err(SoftTimerErr::InvalidParameter)
+        );
+    }
+
+    #[test]
+    fn softtimer_expiry_heap() {
+        let mut heap: ExpiryHeap<u32> = ExpiryHeap::new();
+        for (index, tick) in [7, 3, 9, 3, 1, 8].into_iter().enumerate() {
+            heap.set(index, Some(tick));
+        }
+        heap.set(4, None);
+        heap.set(2, Some(2));
+        heap.set(0, Some(3));
+
+        let mut order = [(0, 0); 5];
+        for entry in order.iter_mut() {
+            *entry = heap.peek().unwrap();
+            heap.set(entry.1, None);
+        }
+        assert_eq!(order, [(2, 2), (3, 0), (3, 1), (3, 3), (8, 5)]);
+        assert_eq!(heap.peek(), None);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn softtimer_advance_to() {
+        let mut rng = Rng(0xc0ff_ee00);
+
+        for _ in 0..200 {
+            let linear = SofTimers::new();
+            let advanced = SofTimers::new();
+            let window = if 0 == rng.next(4) { rng.next(3) } else { 0 };
+            let meddle = rng.next(3);
+            let react = |timers: &SofTimers, handle: SoftTimerHandle| match meddle {
+                1 => timers.remaining_subticks(2).ok(),
+                2 => timers.restart(handle ^ 1).ok().map(|_| (0, 0)),
+                _ => None,
+            };
+
+            for _ in 0..rng.next(MAX_SOFT_COUNTER) {
+                let threshold = rng.next(40);
+                let auto_restart = 0 == rng.next(2);
+                let grace = if 0 == rng.next(4) { rng.next(3) } else { 0 };
+                let unit = 1 + rng.next(3);
+                let min_interval = rng.next(10);
+                let started = !rng.0.is_multiple_of(5);
+
+                for timers in [&linear, &advanced] {
+                    timers.set_coalesce_window(window);
+                    let h = timers.create().unwrap();
+                    assert_eq!(timers.set_unit(h, unit), Ok(()));
+                    assert_eq!(timers.set_min_interval(h, min_interval), Ok(()));
+                    if started {
+                        assert_eq!(
+                            timers.start_with_grace(h, threshold, auto_restart, grace),
+                            Ok(())
+                        );
+                    }
+                }
+            }
+            if 1 < linear.running_count() {
+                for timers in [&linear, &advanced] {
+                    assert_eq!(timers.chain(0, 1), Ok(()));
+                }
+            }
+
+            let mut expected = std::vec::Vec::new();
+            let mut reported = std::vec::Vec::new();
+            for _ in 0..rng.next(5) {
+                let ticks = rng.next(50);
+                for _ in 0..ticks {
+                    linear.update_with(|h| expected.push((linear.now(), h, react(&linear, h))));
+                }
+                advanced.advance_to(advanced.now() + ticks, |h| {
+                    reported.push((advanced.now(), h, react(&advanced, h)))
+                });
+
+                assert_eq!(advanced.now(), linear.now());
+                assert_eq!(advanced.uptime_ticks(), linear.uptime_ticks());
+                assert_eq!(advanced.snapshot_all(), linear.snapshot_all());
+                for (handle, _) in linear.iter_remaining() {
+                    assert_eq!(advanced.expired_at(handle), linear.expired_at(handle));
+                    assert_eq!(
+                        advanced.remaining_subticks(handle),
+                        linear.remaining_subticks(handle)
+                    );
+                    assert_eq!(advanced.is_signaled(handle), linear.is_signaled(handle));
+                }
+            }
+            assert_eq!(reported, expected);
+        }
     }
 }
@@ -11,12 +11,53 @@
 // MODULES
 // ************************************************************************************************
 
+use crate::scheduler::Context;
+use crate::softtimer::TimerSignals;
+use crate::{Signal, SignalError, SignalState, TrySignal};
+#[cfg(feature = "alloc")]
+use alloc::boxed::Box;
+#[cfg(feature = "alloc")]
+use core::any::Any;
+
 // ************************************************************************************************
 // TRAITS
 // ************************************************************************************************
 
-pub trait Execute {
-    fn execute(&mut self, id: TaskId);
+/// Executer of a task. Executers of a scheduler with per-task scratch
+/// values of type `S` implement `Execute<S>`, see Context::scratch().
+pub trait Execute<S = ()> {
+    /// Executes the task. The returned control value tells the scheduler
+    /// how to continue with the task.
+    fn execute(&mut self, id: TaskId) -> TaskControl;
+
+    /// Executes the task with access to its execution context, e.g. to
+    /// exchange messages with other tasks. The default implementation
+    /// ignores the context and calls execute().
+    fn execute_with(&mut self, id: TaskId, ctx: &mut Context<S>) -> TaskControl {
+        let _ = ctx;
+        self.execute(id)
+    }
+}
+
+/// Executer which can be downcast to its concrete type, see
+/// Task::new_inspectable(). Implemented for every `'static` executer.
+#[cfg(feature = "alloc")]
+pub trait AnyExecute<S = ()>: Execute<S> + Any {}
+
+#[cfg(feature = "alloc")]
+impl<S, T: Execute<S> + Any> AnyExecute<S> for T {}
+
+/// Executer which can fail with its own error type.
+///
+/// Tasks store their executer as `dyn Execute`, which can't carry an
+/// associated error type. A `TryExecute` implementation therefore gets
+/// wrapped into a `FallibleExecuter`, which erases the error by passing
+/// it to an error sink and puts the task into `TaskState::Faulted`.
+pub trait TryExecute {
+    type Error;
+
+    /// Executes the task like Execute::execute() or fails with an error.
+    fn try_execute(&mut self, id: TaskId) -> Result<TaskControl, Self::Error>;
 }
 
 // ************************************************************************************************
@@ -28,17 +69,79 @@ pub trait Execute {
 pub type TaskId = usize;
 
 /// Task structure
-pub struct Task<'a> {
+pub struct Task<'a, S = ()> {
     pub state: TaskState,
-    pub func: &'a mut dyn Execute,
+    pub priority: u8,
+    pub phase: u8,
+    pub init: bool,
+    pub func: Executer<'a, S>,
+    name: Option<&'static str>,
+    signal: Option<WaitSignal<'a>>,
+    waiting_cycles: usize,
+    delay: usize,
+    boost: u8,
+    skipped_cycles: u16,
+    last_error: Option<u32>,
+    cancelled: bool,
+}
+
+/// Adapter running a `TryExecute` implementation as task executer.
+/// Errors are passed to the `on_error` sink.
+pub struct FallibleExecuter<T, F> {
+    executer: T,
+    on_error: F,
+}
+
+/// Signal source a waiting task waits for.
+#[derive(Clone, Copy)]
+enum WaitSignal<'a> {
+    Infallible(&'a dyn Signal),
+    Fallible(&'a dyn TrySignal),
+    // The handle is stored as u32 to keep the signal at 3 words, handles
+    // not fitting are mapped to an invalid one.
+    Timer(&'a dyn TimerSignals, u32),
+}
+
+/// Storage of the executer called by a task.
+pub enum Executer<'a, S = ()> {
+    /// Executer owned by the caller and borrowed by the task.
+    Borrowed(&'a mut dyn Execute<S>),
+    /// Executer owned by the task itself.
+    #[cfg(feature = "alloc")]
+    Boxed(Box<dyn Execute<S>>),
+    /// Executer owned by the task itself, which can be downcast.
+    #[cfg(feature = "alloc")]
+    Inspectable(Box<dyn AnyExecute<S>>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+/// Possible results of a task execution.
+pub enum TaskControl {
+    /// Keep the task in the scheduler.
+    Continue,
+    /// The task completed, remove it from the scheduler. The slot is
+    /// freed right after the execution, so a task removes itself by this.
+    Remove,
+    /// The task failed, put it into `TaskState::Faulted`.
+    Fault,
+    /// The task gave up the CPU before finishing its work, e.g. because
+    /// Context::should_yield() was set. It stays in the scheduler.
+    Yield,
+    /// Skip the given number of process cycles, see Task::delay().
+    SleepCycles(usize),
+    /// The task failed with an error code, put it into
+    /// `TaskState::Faulted` and keep the code, see Task::last_error().
+    Error(u32),
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 /// Possible Task States.
 pub enum TaskState {
     Waiting = 0,
     Suspended = 1,
     Running = 2,
+    Delayed = 3,
+    Faulted = 4,
 }
 
 // ************************************************************************************************
@@ -57,11 +160,165 @@ pub const INVALID_ID: usize = usize::MAX;
 // IMPLEMENTATIONS
 // ************************************************************************************************
 
-impl<'a> Task<'a> {
+impl TaskState {
+    /// Converts the state into its numeric representation.
+    ///
+    pub const fn as_u8(self) -> u8 {
+        self as u8
+    }
+
+    /// Converts a numeric representation back into a state.
+    /// Returns `None` for values not representing a state.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lwos::task::TaskState;
+    ///
+    /// assert_eq!(TaskState::try_from_u8(TaskState::Running.as_u8()), Some(TaskState::Running));
+    /// assert_eq!(TaskState::try_from_u8(200), None);
+    /// ```
+    pub const fn try_from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(TaskState::Waiting),
+            1 => Some(TaskState::Suspended),
+            2 => Some(TaskState::Running),
+            3 => Some(TaskState::Delayed),
+            4 => Some(TaskState::Faulted),
+            _ => None,
+        }
+    }
+
+    /// Gets the name of the state for logging.
+    ///
+    pub const fn name(self) -> &'static str {
+        match self {
+            TaskState::Waiting => "Waiting",
+            TaskState::Suspended => "Suspended",
+            TaskState::Running => "Running",
+            TaskState::Delayed => "Delayed",
+            TaskState::Faulted => "Faulted",
+        }
+    }
+}
+
+impl<'a, S> Task<'a, S> {
     /// Initializes a task structure.
     ///
-    pub fn new(state: TaskState, func: &'a mut dyn Execute) -> Self {
-        Task { state, func }
+    pub fn new(state: TaskState, func: &'a mut dyn Execute<S>) -> Self {
+        Self::with_executer(state, Executer::Borrowed(func))
+    }
+
+    /// Initializes a task structure with a name used for logging and
+    /// lookup by Scheduler::find_by_name().
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lwos::task::{Task, TaskState, TaskId, Execute, TaskControl};
+    ///
+    /// struct SomeExecuter {}
+    /// impl Execute for SomeExecuter {
+    ///     fn execute(&mut self, _id : TaskId) -> TaskControl {
+    ///         TaskControl::Continue
+    ///     }
+    /// }
+    /// let mut executer = SomeExecuter {};
+    /// let t = Task::named(TaskState::Running, "blink", &mut executer);
+    /// assert_eq!(t.name(), Some("blink"));
+    /// ```
+    pub fn named(state: TaskState, name: &'static str, func: &'a mut dyn Execute<S>) -> Self {
+        let mut task = Self::new(state, func);
+        task.name = Some(name);
+        task
+    }
+
+    /// Initializes a task structure which takes ownership of its executer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lwos::task::{Task, TaskState, TaskId, Execute, TaskControl};
+    ///
+    /// struct SomeExecuter {}
+    /// impl Execute for SomeExecuter {
+    ///     fn execute(&mut self, _id : TaskId) -> TaskControl {
+    ///         TaskControl::Continue
+    ///     }
+    /// }
+    /// let t = Task::new_boxed(TaskState::Running, Box::new(SomeExecuter {}));
+    /// assert_eq!(t.state, TaskState::Running);
+    /// ```
+    #[cfg(feature = "alloc")]
+    pub fn new_boxed(state: TaskState, func: Box<dyn Execute<S>>) -> Self {
+        Self::with_executer(state, Executer::Boxed(func))
+    }
+
+    /// Initializes a task structure which takes ownership of its executer
+    /// like new_boxed(). The executer can be accessed by its concrete type
+    /// through Scheduler::get_executer_mut().
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lwos::task::{Task, TaskState, TaskId, Execute, TaskControl};
+    ///
+    /// struct SomeExecuter {}
+    /// impl Execute for SomeExecuter {
+    ///     fn execute(&mut self, _id : TaskId) -> TaskControl {
+    ///         TaskControl::Continue
+    ///     }
+    /// }
+    /// let t = Task::new_inspectable(TaskState::Running, Box::new(SomeExecuter {}));
+    /// assert_eq!(t.state, TaskState::Running);
+    /// ```
+    #[cfg(feature = "alloc")]
+    pub fn new_inspectable(state: TaskState, func: Box<dyn AnyExecute<S>>) -> Self {
+        Self::with_executer(state, Executer::Inspectable(func))
+    }
+
+    /// Initializes a task structure for any kind of executer storage.
+    ///
+    fn with_executer(state: TaskState, func: Executer<'a, S>) -> Self {
+        Task {
+            state,
+            priority: 0,
+            phase: 0,
+            init: false,
+            func,
+            name: None,
+            signal: None,
+            waiting_cycles: 0,
+            delay: 0,
+            boost: 0,
+            skipped_cycles: 0,
+            last_error: None,
+            cancelled: false,
+        }
+    }
+
+    /// Gets the priority including the boost gained by aging.
+    ///
+    pub fn effective_priority(&self) -> u8 {
+        self.priority.saturating_add(self.boost)
+    }
+
+    /// Counts a cycle in which the task was ready but not selected and
+    /// raises its boost by one every `period` skipped cycles.
+    ///
+    pub(crate) fn age(&mut self, period: usize) {
+        self.skipped_cycles = self.skipped_cycles.saturating_add(1);
+
+        if usize::from(self.skipped_cycles) >= period {
+            self.skipped_cycles = 0;
+            self.boost = self.boost.saturating_add(1);
+        }
+    }
+
+    /// Gets the name of the task, if it has one.
+    ///
+    pub fn name(&self) -> Option<&'static str> {
+        self.name
     }
 
     /// Suspends a task to no longer schedule it
@@ -69,11 +326,12 @@ impl<'a> Task<'a> {
     /// # Examples
     ///
     /// ```
-    /// use lwos::task::{Task, TaskState, TaskId, Execute};
+    /// use lwos::task::{Task, TaskState, TaskId, Execute, TaskControl};
     ///
     /// struct SomeExecuter {}
     /// impl Execute for SomeExecuter {
-    ///     fn execute(&mut self, _id : TaskId) {
+    ///     fn execute(&mut self, _id : TaskId) -> TaskControl {
+    ///         TaskControl::Continue
     ///     }
     /// }
     /// let mut executer = SomeExecuter {};
@@ -90,11 +348,12 @@ impl<'a> Task<'a> {
     /// # Examples
     ///
     /// ```
-    /// use lwos::task::{Task, TaskState, TaskId, Execute};
+    /// use lwos::task::{Task, TaskState, TaskId, Execute, TaskControl};
     ///
     /// struct SomeExecuter {}
     /// impl Execute for SomeExecuter {
-    ///     fn execute(&mut self, _id : TaskId) {
+    ///     fn execute(&mut self, _id : TaskId) -> TaskControl {
+    ///         TaskControl::Continue
     ///     }
     /// }
     /// let mut executer = SomeExecuter {};
@@ -102,24 +361,253 @@ impl<'a> Task<'a> {
     /// t.resume();
     /// assert_eq!(t.state, TaskState::Running);
     /// ```
-
     pub fn resume(&mut self) {
         self.state = TaskState::Running;
     }
 
-    /// Tries to execute the task dependend on status
+    /// Puts a task into waiting state until the given signal fires.
+    /// The task resumes and executes in the cycle the signal is seen.
     ///
-    pub fn process(&mut self, id: TaskId) {
-        match self.state {
-            TaskState::Running => {
-                self.func.execute(id);
-            }
-            TaskState::Waiting => {
-                {
-                    // TODO: Signal processing
+    pub fn wait(&mut self, signal: &'a dyn Signal) {
+        self.wait_for(WaitSignal::Infallible(signal));
+    }
+
+    /// Puts a task into waiting state like wait(), but for a signal source
+    /// which may fail. If the source reports an error, the task goes into
+    /// `TaskState::Faulted` instead of waiting forever.
+    ///
+    pub fn wait_fallible(&mut self, signal: &'a dyn TrySignal) {
+        self.wait_for(WaitSignal::Fallible(signal));
+    }
+
+    /// Puts a task into waiting state until the given soft timer signals.
+    /// A deleted timer puts the task into `TaskState::Faulted` like a
+    /// failing signal of wait_fallible(). The pool may use any counter
+    /// type, see SofTimers::with_counter().
+    ///
+    pub fn wait_timer(&mut self, timers: &'a dyn TimerSignals, handle: usize) {
+        let handle = u32::try_from(handle).unwrap_or(u32::MAX);
+        self.wait_for(WaitSignal::Timer(timers, handle));
+    }
+
+    fn wait_for(&mut self, signal: WaitSignal<'a>) {
+        self.signal = Some(signal);
+        self.waiting_cycles = 0;
+        self.state = TaskState::Waiting;
+    }
+
+    /// Delays a task for the given number of process cycles. The task
+    /// starts running in the cycle after the delay elapsed.
+    ///
+    pub fn delay(&mut self, cycles: usize) {
+        self.delay = cycles;
+        self.state = TaskState::Delayed;
+    }
+
+    /// Gets the number of consecutive cycles the task has been waiting
+    /// for its signal.
+    ///
+    pub fn waiting_cycles(&self) -> usize {
+        self.waiting_cycles
+    }
+
+    /// Gets the error code of the last execution which returned
+    /// `TaskControl::Error`, e.g. for a status screen. The code is kept
+    /// until clear_error() is called.
+    ///
+    pub fn last_error(&self) -> Option<u32> {
+        self.last_error
+    }
+
+    /// Clears the last error code of the task.
+    ///
+    pub fn clear_error(&mut self) {
+        self.last_error = None;
+    }
+
+    /// Requests the task to stop. The task sees the request by
+    /// Context::is_cancelled() and is expected to clean up and return
+    /// `TaskControl::Remove`. A waiting or delayed task is resumed, so it
+    /// gets the chance to do so.
+    ///
+    pub fn cancel(&mut self) {
+        self.cancelled = true;
+        if matches!(self.state, TaskState::Waiting | TaskState::Delayed) {
+            self.resume();
+        }
+    }
+
+    /// Checks if the task was requested to stop, see cancel().
+    ///
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled
+    }
+
+    /// Tries to execute the task dependend on status. Returns the result
+    /// of the execution or `None` if the task was not executed.
+    ///
+    pub fn process(&mut self, id: TaskId) -> Option<TaskControl> {
+        self.process_with(id, &mut Context::detached(id))
+    }
+
+    /// Tries to execute the task like process() with the given context.
+    ///
+    pub fn process_with(&mut self, id: TaskId, ctx: &mut Context<S>) -> Option<TaskControl> {
+        if self.prepare() {
+            Some(self.run(id, ctx))
+        } else {
+            None
+        }
+    }
+
+    /// Updates the state of the task for a process cycle. Returns true if
+    /// the task is running and has to be executed by run().
+    ///
+    pub(crate) fn prepare(&mut self) -> bool {
+        if TaskState::Waiting == self.state {
+            let signal_state = match self.signal {
+                Some(WaitSignal::Infallible(s)) => Ok(s.get_signal_state()),
+                Some(WaitSignal::Fallible(s)) => s.try_get_signal_state(),
+                Some(WaitSignal::Timer(timers, handle)) => {
+                    match timers.is_signaled(handle as usize) {
+                        Ok(true) => Ok(SignalState::Signaled),
+                        Ok(false) => Ok(SignalState::NotSignaled),
+                        Err(_) => Err(SignalError),
+                    }
+                }
+                None => Ok(SignalState::NotSignaled),
+            };
+
+            match signal_state {
+                Ok(SignalState::Signaled) => {
+                    self.signal = None;
+                    self.state = TaskState::Running;
                 }
+                Ok(SignalState::NotSignaled) => {
+                    self.waiting_cycles = self.waiting_cycles.saturating_add(1);
+                }
+                Err(SignalError) => {
+                    self.signal = None;
+                    self.state = TaskState::Faulted;
+                }
+            }
+        }
+
+        if TaskState::Delayed == self.state {
+            if 0 < self.delay {
+                self.delay -= 1;
+            } else {
+                self.state = TaskState::Running;
+            }
+        }
+
+        if TaskState::Running == self.state {
+            self.waiting_cycles = 0;
+            self.boost = 0;
+            self.skipped_cycles = 0;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Executes a task prepared by prepare().
+    ///
+    pub(crate) fn run(&mut self, id: TaskId, ctx: &mut Context<S>) -> TaskControl {
+        let control = self.func.get().execute_with(id, ctx);
+        match control {
+            TaskControl::Fault => self.state = TaskState::Faulted,
+            TaskControl::SleepCycles(cycles) => self.delay(cycles),
+            TaskControl::Error(code) => {
+                self.last_error = Some(code);
+                self.state = TaskState::Faulted;
+            }
+            _ => (),
+        }
+        control
+    }
+}
+
+/// Gets the memory needed to store a single task, e.g. for static memory
+/// budgeting. A scheduler needs about `SIZE` times this value.
+///
+/// # Examples
+///
+/// ```
+/// use lwos::task::task_footprint;
+///
+/// assert!(task_footprint() > 0);
+/// ```
+pub const fn task_footprint() -> usize {
+    core::mem::size_of::<Task>()
+}
+
+impl<'a, S> Executer<'a, S> {
+    /// Gets the executer regardless of how it is stored.
+    ///
+    pub fn get(&mut self) -> &mut (dyn Execute<S> + 'a) {
+        match self {
+            Executer::Borrowed(func) => &mut **func,
+            #[cfg(feature = "alloc")]
+            Executer::Boxed(func) => func.as_mut(),
+            #[cfg(feature = "alloc")]
+            Executer::Inspectable(func) => func.as_mut(),
+        }
+    }
+
+    /// Gets the executer as `Any` for downcasting, if it was stored by
+    /// Task::new_inspectable().
+    ///
+    #[cfg(feature = "alloc")]
+    pub fn as_any_mut(&mut self) -> Option<&mut dyn Any> {
+        match self {
+            Executer::Inspectable(func) => Some(func.as_mut()),
+            _ => None,
+        }
+    }
+}
+
+impl<T, F> FallibleExecuter<T, F>
+where
+    T: TryExecute,
+    F: FnMut(TaskId, T::Error),
+{
+    /// Wraps a fallible executer and the sink receiving its errors.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lwos::task::{FallibleExecuter, Task, TaskControl, TaskId, TaskState, TryExecute};
+    ///
+    /// struct Sensor {}
+    /// impl TryExecute for Sensor {
+    ///     type Error = &'static str;
+    ///     fn try_execute(&mut self, _id: TaskId) -> Result<TaskControl, Self::Error> {
+    ///         Err("no response")
+    ///     }
+    /// }
+    /// let mut executer = FallibleExecuter::new(Sensor {}, |_id, _err| {});
+    /// let mut t = Task::new(TaskState::Running, &mut executer);
+    /// assert_eq!(t.process(0), Some(TaskControl::Fault));
+    /// assert_eq!(t.state, TaskState::Faulted);
+    /// ```
+    pub fn new(executer: T, on_error: F) -> Self {
+        FallibleExecuter { executer, on_error }
+    }
+}
+
+impl<T, F> Execute for FallibleExecuter<T, F>
+where
+    T: TryExecute,
+    F: FnMut(TaskId, T::Error),
+{
+    fn execute(&mut self, id: TaskId) -> TaskControl {
+        match self.executer.try_execute(id) {
+            Ok(control) => control,
+            Err(err) => {
+                (self.on_error)(id, err);
+                TaskControl::Fault
             }
-            TaskState::Suspended => (),
         }
     }
 }
@@ -134,7 +622,9 @@ mod tests {
 
     struct SomeExecuter {}
     impl Execute for SomeExecuter {
-        fn execute(&mut self, _id: TaskId) {}
+        fn execute(&mut self, _id: TaskId) -> TaskControl {
+            TaskControl::Continue
+        }
     }
 
     #[test]
@@ -155,4 +645,143 @@ mod tests {
         t.suspend();
         assert_eq!(t.state, TaskState::Suspended);
     }
+
+    #[test]
+    fn task_footprint_budget() {
+        // Executer (2 words, 3 with alloc), name (2 words) and signal
+        // (3 words) are fat pointers, state, priority, phase, aging and
+        // the last error code share 2 words, the counters need 1 word each
+        // (11/12 words). Every field added to Task grows each scheduler
+        // slot, so raise the budget only on purpose.
+        const BUDGET: usize = 12 * core::mem::size_of::<usize>();
+
+        assert!(task_footprint() <= BUDGET);
+    }
+
+    struct StubSignal {
+        state: core::cell::Cell<bool>,
+    }
+    impl Signal for StubSignal {
+        fn get_signal_state(&self) -> SignalState {
+            if self.state.get() {
+                SignalState::Signaled
+            } else {
+                SignalState::NotSignaled
+            }
+        }
+    }
+
+    #[test]
+    fn task_wait_signal() {
+        let signal = StubSignal {
+            state: core::cell::Cell::new(false),
+        };
+        let mut task_executer: SomeExecuter = SomeExecuter {};
+        let mut t = Task::new(TaskState::Running, &mut task_executer);
+
+        t.wait(&signal);
+        assert_eq!(t.process(0), None);
+        assert_eq!(t.process(0), None);
+        assert_eq!(t.state, TaskState::Waiting);
+        assert_eq!(t.waiting_cycles(), 2);
+
+        signal.state.set(true);
+        assert_eq!(t.process(0), Some(TaskControl::Continue));
+        assert_eq!(t.state, TaskState::Running);
+        assert_eq!(t.waiting_cycles(), 0);
+    }
+
+    struct FaultySignal {}
+    impl TrySignal for FaultySignal {
+        fn try_get_signal_state(&self) -> Result<SignalState, SignalError> {
+            Err(SignalError)
+        }
+    }
+
+    #[test]
+    fn task_wait_faulty_signal() {
+        let signal = FaultySignal {};
+        let mut task_executer: SomeExecuter = SomeExecuter {};
+        let mut t = Task::new(TaskState::Running, &mut task_executer);
+
+        t.wait_fallible(&signal);
+        assert_eq!(t.process(0), None);
+        assert_eq!(t.state, TaskState::Faulted);
+        assert_eq!(t.process(0), None);
+        assert_eq!(t.state, TaskState::Faulted);
+
+        t.resume();
+        assert_eq!(t.process(0), Some(TaskControl::Continue));
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    enum SensorError {
+        Timeout,
+    }
+
+    struct SensorExecuter {
+        runs: usize,
+    }
+    impl TryExecute for SensorExecuter {
+        type Error = SensorError;
+
+        fn try_execute(&mut self, _id: TaskId) -> Result<TaskControl, SensorError> {
+            self.runs += 1;
+            if 2 == self.runs {
+                Err(SensorError::Timeout)
+            } else {
+                Ok(TaskControl::Continue)
+            }
+        }
+    }
+
+    #[test]
+    fn task_try_execute_error() {
+        let reported = core::cell::Cell::new(None);
+        let mut task_executer = FallibleExecuter::new(SensorExecuter { runs: 0 }, |id, err| {
+            reported.set(Some((id, err)))
+        });
+        let mut t = Task::new(TaskState::Running, &mut task_executer);
+
+        assert_eq!(t.process(7), Some(TaskControl::Continue));
+        assert_eq!(reported.get(), None);
+
+        assert_eq!(t.process(7), Some(TaskControl::Fault));
+        assert_eq!(t.state, TaskState::Faulted);
+        assert_eq!(t.process(7), None);
+        assert_eq!(reported.get(), Some((7, SensorError::Timeout)));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn task_boxed_process() {
+        use alloc::rc::Rc;
+        use core::cell::Cell;
+
+        struct CountExecuter {
+            count: Rc<Cell<usize>>,
+        }
+        impl Execute for CountExecuter {
+            fn execute(&mut self, _id: TaskId) -> TaskControl {
+                self.count.set(self.count.get() + 1);
+                TaskControl::Continue
+            }
+        }
+
+        let count = Rc::new(Cell::new(0usize));
+        let mut t = Task::new_boxed(
+            TaskState::Running,
+            Box::new(CountExecuter {
+                count: count.clone(),
+            }),
+        );
+
+        t.process(0);
+        t.process(0);
+        assert_eq!(count.get(), 2);
+
+        t.suspend();
+        t.process(0);
+        assert_eq!(count.get(), 2);
+    }
 }